@@ -1,17 +1,63 @@
 use std::{
     fs::File,
-    io::{Read, Write},
+    io::{self, Read, Write},
     path::Path,
 };
 
-use clap::Parser;
-use formatter::format::{FormatOptions, Formatter};
+use clap::{Parser, ValueEnum};
+use formatter::format::{
+    detect_line_ending, find_long_lines, find_mixed_indentation, Dialect, FormatOptions, Formatter,
+    LineEnding,
+};
+use json_parser::{
+    encoding::{decode, detect_bom, encode, Encoding},
+    parse::{ParseOptions, Parser as JsonParser},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum InputDialect {
+    /// strict JSON
+    Json,
+    /// JSON with comments, written back as plain JSON
+    Jsonc,
+    /// lenient input, written back as JSON5
+    Json5,
+}
+
+impl InputDialect {
+    fn parse_options(self) -> ParseOptions {
+        ParseOptions {
+            lenient: self == InputDialect::Json5,
+            allow_comments: self != InputDialect::Json,
+            allow_trailing_commas: self != InputDialect::Json,
+            allow_unquoted_keys: self == InputDialect::Json5,
+            allow_single_quotes: self == InputDialect::Json5,
+            allow_non_finite: self == InputDialect::Json5,
+            // spans let the comments be kept
+            spans: self != InputDialect::Json,
+            ..Default::default()
+        }
+    }
+
+    fn format_dialect(self) -> Dialect {
+        match self {
+            InputDialect::Json | InputDialect::Jsonc => Dialect::Json,
+            InputDialect::Json5 => Dialect::Json5,
+        }
+    }
+}
 
 #[derive(Debug, Parser)]
 #[clap(version = "0.1.0", author = "sor4chi")]
 struct Args {
-    #[arg(help = "file path to format, default is stdin")]
-    file_path: Option<String>,
+    #[arg(help = "file paths to format, default is stdin")]
+    file_paths: Vec<String>,
+
+    #[arg(
+        long = "files-from",
+        help = "also format the newline-separated paths listed in this file, - for stdin"
+    )]
+    files_from: Option<String>,
 
     #[arg(
         short = 't',
@@ -39,49 +85,312 @@ struct Args {
         require_equals = true
     )]
     trailing_commas: Option<bool>,
+
+    #[arg(
+        long = "crlf",
+        help = "end lines with CRLF, --crlf=false forces LF, default keeps the input's",
+        default_missing_value = "true",
+        num_args = 0..=1,
+        require_equals = true
+    )]
+    crlf: Option<bool>,
+
+    #[arg(
+        long = "final-newline",
+        help = "end the output with a line break, default is true",
+        default_missing_value = "true",
+        num_args = 0..=1,
+        require_equals = true
+    )]
+    final_newline: Option<bool>,
+
+    #[arg(
+        long = "reindent",
+        help = "only normalize indentation, keeping every value on its original line",
+        default_missing_value = "true",
+        num_args = 0..=1,
+        require_equals = true
+    )]
+    reindent: Option<bool>,
+
+    #[arg(
+        long = "check",
+        help = "only report files that are not formatted, without writing them",
+        default_missing_value = "true",
+        num_args = 0..=1,
+        require_equals = true
+    )]
+    check: Option<bool>,
+
+    #[arg(
+        long = "stdout",
+        help = "print the formatted files to stdout instead of rewriting them",
+        default_missing_value = "true",
+        num_args = 0..=1,
+        require_equals = true
+    )]
+    stdout: Option<bool>,
+
+    #[arg(
+        long = "allow-empty",
+        help = "leave empty input untouched instead of failing",
+        default_missing_value = "true",
+        num_args = 0..=1,
+        require_equals = true
+    )]
+    allow_empty: Option<bool>,
+
+    #[arg(
+        short = 'q',
+        long = "quiet",
+        help = "print nothing but errors, report check failures through the exit code only",
+        default_missing_value = "true",
+        num_args = 0..=1,
+        require_equals = true,
+        conflicts_with = "verbose"
+    )]
+    quiet: Option<bool>,
+
+    #[arg(
+        short = 'v',
+        long = "verbose",
+        help = "print every processed file and whether it changed, or with --check whether it is formatted",
+        default_missing_value = "true",
+        num_args = 0..=1,
+        require_equals = true
+    )]
+    verbose: Option<bool>,
+
+    #[arg(
+        long = "dialect",
+        value_enum,
+        help = "input and output dialect, default is json"
+    )]
+    dialect: Option<InputDialect>,
+
+    #[arg(
+        long = "max-line-length",
+        help = "fail if any formatted line is longer than the given number of characters"
+    )]
+    max_line_length: Option<usize>,
 }
 
 fn main() {
     let args = Args::parse();
-    let fp = match args.file_path {
-        Some(path) => path,
-        None => {
-            panic!("file path is required");
+    match run(&args) {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Formats every input, returning whether all of them passed. Files that
+/// cannot be formatted are reported and skipped; other failures abort.
+fn run(args: &Args) -> Result<bool, String> {
+    let mut paths = args.file_paths.clone();
+    if let Some(source) = &args.files_from {
+        paths.extend(read_file_list(source)?);
+    }
+
+    let mut passed = true;
+    if args.file_paths.is_empty() && args.files_from.is_none() {
+        passed = format_stdin(args)?;
+    }
+    for path in &paths {
+        match format_file(path, args) {
+            Ok(file_passed) => passed &= file_passed,
+            Err(e) => {
+                eprintln!("error: {}", e);
+                passed = false;
+            }
         }
+    }
+    Ok(passed)
+}
+
+/// Reads newline-separated paths from `source`, or from stdin when it is `-`.
+fn read_file_list(source: &str) -> Result<Vec<String>, String> {
+    let mut list = String::new();
+    let result = if source == "-" {
+        io::stdin().read_to_string(&mut list)
+    } else {
+        File::open(source).and_then(|mut file| file.read_to_string(&mut list))
     };
-    let path = Path::new(&fp);
-    let mut file = match File::open(path) {
-        Ok(file) => file,
-        Err(e) => {
-            panic!("failed to open file: {}", e);
+    if let Err(e) = result {
+        return Err(format!("{}: failed to read file list: {}", source, e));
+    }
+    Ok(list
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| line.to_string())
+        .collect())
+}
+
+/// Formats stdin to stdout, returning false if it failed a check.
+fn format_stdin(args: &Args) -> Result<bool, String> {
+    let mut bytes = Vec::new();
+    if let Err(e) = io::stdin().read_to_end(&mut bytes) {
+        return Err(format!("failed to read stdin: {}", e));
+    }
+    let (buf, formatted) = match format_bytes("<stdin>", &bytes, args)? {
+        Some(result) => result,
+        None => return Ok(false),
+    };
+    if args.check.unwrap_or(false) {
+        return Ok(buf.trim().is_empty() || check_formatted("<stdin>", &buf, &formatted, args));
+    }
+    write_stdout(&formatted)?;
+    Ok(true)
+}
+
+fn write_stdout(formatted: &str) -> Result<(), String> {
+    io::stdout()
+        .write_all(formatted.as_bytes())
+        .map_err(|e| format!("failed to write stdout: {}", e))
+}
+
+/// Formats the file at `fp` in place, returning false if it failed a check.
+fn format_file(fp: &str, args: &Args) -> Result<bool, String> {
+    let path = Path::new(fp);
+    let mut bytes = Vec::new();
+    if let Err(e) = File::open(path).and_then(|mut file| file.read_to_end(&mut bytes)) {
+        return Err(format!("{}: failed to read file: {}", fp, e));
+    }
+    let (buf, formatted) = match format_bytes(fp, &bytes, args)? {
+        Some(result) => result,
+        None => return Ok(false),
+    };
+    if buf.trim().is_empty() {
+        // accepted empty input is left untouched
+        return Ok(true);
+    }
+    if args.check.unwrap_or(false) {
+        return Ok(check_formatted(fp, &buf, &formatted, args));
+    }
+    if args.stdout.unwrap_or(false) {
+        write_stdout(&formatted)?;
+        return Ok(true);
+    }
+
+    // write back in the encoding and with the byte order mark it was read with
+    let bom = detect_bom(&bytes);
+    let encoded = match encode(&formatted, bom.unwrap_or(Encoding::Utf8), bom.is_some()) {
+        Ok(encoded) => encoded,
+        Err(e) => return Err(format!("{}: failed to encode: {}", fp, e)),
+    };
+    if let Err(e) = File::create(path).and_then(|mut file| file.write_all(&encoded)) {
+        return Err(format!("{}: failed to write file: {}", fp, e));
+    }
+    if args.verbose.unwrap_or(false) {
+        if formatted == buf {
+            println!("unchanged {}", fp);
+        } else {
+            println!("formatted {}", fp);
+        }
+    }
+    Ok(true)
+}
+
+/// Compares the input with its formatted form, reporting `fp` and any lines
+/// indented with both tabs and spaces when they differ.
+fn check_formatted(fp: &str, buf: &str, formatted: &str, args: &Args) -> bool {
+    if args.verbose.unwrap_or(false) {
+        if buf == formatted {
+            println!("ok {}", fp);
+        } else {
+            println!("not formatted {}", fp);
         }
+    }
+    if buf == formatted {
+        return true;
+    }
+    if !args.quiet.unwrap_or(false) {
+        eprintln!("{}: not formatted", fp);
+        for line_number in find_mixed_indentation(buf) {
+            eprintln!("{}:{}: indentation mixes tabs and spaces", fp, line_number);
+        }
+    }
+    false
+}
+
+/// Decodes and formats `bytes` read from `fp`, returning the decoded input
+/// and its formatted form, or None if it failed to parse or failed a check.
+/// Accepted empty input formats to nothing.
+fn format_bytes(fp: &str, bytes: &[u8], args: &Args) -> Result<Option<(String, String)>, String> {
+    let encoding = detect_bom(bytes).unwrap_or(Encoding::Utf8);
+    let buf = match decode(bytes, encoding) {
+        Ok(buf) => buf,
+        Err(e) => return Err(format!("{}: failed to decode: {}", fp, e)),
     };
-    let mut buf = String::new();
-    match file.read_to_string(&mut buf) {
-        Ok(_) => {}
-        Err(e) => {
-            panic!("failed to read file: {}", e);
+    if buf.trim().is_empty() {
+        if args.allow_empty.unwrap_or(false) {
+            return Ok(Some((buf, String::new())));
         }
+        return Err(format!(
+            "{}: input is empty, pass --allow-empty to accept it",
+            fp
+        ));
     }
+
+    let dialect = args.dialect.unwrap_or(InputDialect::Json);
     let mut formatter = Formatter::new(Some(FormatOptions {
         use_tabs: args.use_tabs.unwrap_or(false),
         spaces: args.spaces.unwrap_or(4),
         trailing_commas: args.trailing_commas.unwrap_or(false),
+        dialect: dialect.format_dialect(),
+        line_ending: match args.crlf {
+            Some(true) => LineEnding::Crlf,
+            Some(false) => LineEnding::Lf,
+            None => detect_line_ending(&buf),
+        },
+        final_newline: args.final_newline.unwrap_or(true),
+        ..Default::default()
     }));
-    let formatted = formatter.format(&buf);
-
-    // write to file
-    let mut file = match File::create(path) {
-        Ok(file) => file,
+    // parsed even when only reindenting, which does not check the input
+    let mut parser = JsonParser::with_options(&buf, dialect.parse_options());
+    let node = match parser.try_parse() {
+        Ok(node) => node,
         Err(e) => {
-            panic!("failed to create file: {}", e);
+            // path:line:col: first, for editors to jump to
+            let (line, column) = e.line_col(&buf);
+            eprintln!("{}:{}:{}: {}", fp, line, column, e.message);
+            return Ok(None);
         }
     };
+    let comments = parser.comments();
+    drop(parser);
+    let mut formatted = if args.reindent.unwrap_or(false) {
+        formatter.reindent(&buf)
+    } else if !comments.is_empty() {
+        formatter.format_node_with_comments(&node, &comments)
+    } else {
+        formatter
+            .try_format_node(&node)
+            .map_err(|e| format!("{}: {}", fp, e))?
+    };
+    formatter.push_final_newline(&mut formatted);
 
-    match file.write_all(formatted.as_bytes()) {
-        Ok(_) => {}
-        Err(e) => {
-            panic!("failed to write file: {}", e);
+    if let Some(max_line_length) = args.max_line_length {
+        let long_lines = find_long_lines(&formatted, max_line_length);
+        if !long_lines.is_empty() {
+            if args.quiet.unwrap_or(false) {
+                return Ok(None);
+            }
+            for (line_number, line) in long_lines {
+                eprintln!(
+                    "{}:{}: line is {} characters long, max is {}",
+                    fp,
+                    line_number,
+                    line.chars().count(),
+                    max_line_length
+                );
+            }
+            return Ok(None);
         }
     }
+    Ok(Some((buf, formatted)))
 }
@@ -0,0 +1,398 @@
+use std::{
+    fs,
+    io::Write,
+    path::PathBuf,
+    process::{Command, Output, Stdio},
+};
+
+fn temp_file(name: &str, contents: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("json-parser-cli-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_cli"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_empty_input_is_an_error() {
+    let path = temp_file("empty_error.json", "");
+    let output = run(&[path.to_str().unwrap()]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--allow-empty"));
+    assert_eq!(fs::read_to_string(&path).unwrap(), "");
+}
+
+#[test]
+fn test_allow_empty_keeps_empty_input() {
+    let path = temp_file("empty_allowed.json", "");
+    let output = run(&[path.to_str().unwrap(), "--allow-empty"]);
+    assert!(output.status.success());
+    assert_eq!(fs::read_to_string(&path).unwrap(), "");
+}
+
+fn run_with_stdin(args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_cli"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn test_stdin_to_stdout() {
+    let cases = vec![
+        (
+            vec![],
+            r#"{"a": [1, 2]}"#,
+            Some("{\n    \"a\": [\n        1,\n        2\n    ]\n}\n"),
+        ),
+        (vec!["--spaces=2"], "[true]", Some("[\n  true\n]\n")),
+        (vec![], "[true]\n\n", Some("[\n    true\n]\n")),
+        (
+            vec!["--final-newline=false"],
+            "[true]\n",
+            Some("[\n    true\n]"),
+        ),
+        (vec!["--allow-empty"], "", Some("")),
+        (vec![], "", None),
+    ];
+
+    for (args, input, expected) in cases {
+        let output = run_with_stdin(&args, input);
+        match expected {
+            Some(expected) => {
+                assert!(output.status.success(), "{}", input);
+                assert_eq!(String::from_utf8_lossy(&output.stdout), expected);
+            }
+            None => {
+                assert!(!output.status.success(), "{}", input);
+                assert!(String::from_utf8_lossy(&output.stderr).contains("<stdin>"));
+            }
+        }
+    }
+}
+
+#[test]
+fn test_check() {
+    let formatted = temp_file("check_formatted.json", "{\n    \"a\": 1\n}\n");
+    let unformatted = temp_file("check_unformatted.json", "{\n\t\"a\": [\n    1]}");
+
+    let output = run(&[formatted.to_str().unwrap(), "--check"]);
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+
+    let list = temp_file(
+        "check_list.txt",
+        &format!("{}\n{}\n", formatted.display(), unformatted.display()),
+    );
+    let output = run(&["--check", &format!("--files-from={}", list.display())]);
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains(&format!("{}: not formatted", unformatted.display())));
+    assert!(stderr.contains(&format!("{}:3: indentation mixes", unformatted.display())));
+    assert!(!stderr.contains(&formatted.display().to_string()));
+    assert_eq!(
+        fs::read_to_string(&unformatted).unwrap(),
+        "{\n\t\"a\": [\n    1]}"
+    );
+
+    let output = run(&[unformatted.to_str().unwrap(), "--check", "--quiet"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    assert!(output.stderr.is_empty());
+
+    let output = run_with_stdin(&["--check"], "[1]");
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn test_multiple_files() {
+    let first = temp_file("multiple_first.json", r#"{"a": 1}"#);
+    let broken = temp_file("multiple_broken.json", "[1,");
+    let second = temp_file("multiple_second.json", "[1]");
+
+    let output = run(&[first.to_str().unwrap(), second.to_str().unwrap()]);
+    assert!(output.status.success());
+    assert_eq!(fs::read_to_string(&first).unwrap(), "{\n    \"a\": 1\n}\n");
+    assert_eq!(fs::read_to_string(&second).unwrap(), "[\n    1\n]\n");
+
+    let missing = first.with_file_name("multiple_missing.json");
+    fs::write(&first, r#"{"a": 1}"#).unwrap();
+    fs::write(&second, "[1]").unwrap();
+    let output = run(&[
+        first.to_str().unwrap(),
+        broken.to_str().unwrap(),
+        missing.to_str().unwrap(),
+        second.to_str().unwrap(),
+    ]);
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains(&format!("{}:1:4: ", broken.display())));
+    assert!(stderr.contains(&format!("{}: failed to read file", missing.display())));
+    assert_eq!(fs::read_to_string(&first).unwrap(), "{\n    \"a\": 1\n}\n");
+    assert_eq!(fs::read_to_string(&broken).unwrap(), "[1,");
+    assert_eq!(fs::read_to_string(&second).unwrap(), "[\n    1\n]\n");
+}
+
+#[test]
+fn test_parse_error_location() {
+    let path = temp_file("parse_error.json", "{\n    \"a\" 1\n}");
+    let output = run(&[path.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        format!("{}:2:9: Expected `:`\n", path.display())
+    );
+
+    let output = run_with_stdin(&[], "[1, @]");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("<stdin>:1:5: "));
+}
+
+#[test]
+fn test_stdout_leaves_file_untouched() {
+    let path = temp_file("stdout.json", r#"{"a": [1]}"#);
+    let output = run(&[path.to_str().unwrap(), "--stdout"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "{\n    \"a\": [\n        1\n    ]\n}\n"
+    );
+    assert_eq!(fs::read_to_string(&path).unwrap(), r#"{"a": [1]}"#);
+}
+
+#[test]
+fn test_missing_file_is_a_clean_error() {
+    let path = temp_file("missing_sibling.json", "").with_file_name("missing.json");
+    let output = run(&[path.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.starts_with(&format!("error: {}: failed to read file", path.display())));
+    assert!(!stderr.contains("panicked"));
+
+    let output = run(&["--files-from=missing-list.txt"]);
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.starts_with("error: missing-list.txt: failed to read file list"));
+}
+
+#[test]
+fn test_in_place_keeps_encoding() {
+    let utf16le = |text: &str| -> Vec<u8> {
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+        bytes
+    };
+    let utf8_bom = |text: &str| -> Vec<u8> {
+        let mut bytes = vec![0xEF, 0xBB, 0xBF];
+        bytes.extend_from_slice(text.as_bytes());
+        bytes
+    };
+    let cases = vec![
+        ("encoding_utf16.json", utf16le as fn(&str) -> Vec<u8>),
+        ("encoding_utf8_bom.json", utf8_bom),
+    ];
+
+    for (name, encode) in cases {
+        let path = temp_file(name, "");
+        fs::write(&path, encode("{\"caf\u{e9}\": [1]}")).unwrap();
+        let output = run(&[path.to_str().unwrap()]);
+        assert!(output.status.success(), "{}", name);
+        assert_eq!(
+            fs::read(&path).unwrap(),
+            encode("{\n    \"caf\u{e9}\": [\n        1\n    ]\n}\n"),
+            "{}",
+            name
+        );
+    }
+}
+
+#[test]
+fn test_crlf() {
+    let cases = vec![
+        (vec![], "{\"a\": 1}", "{\n    \"a\": 1\n}\n"),
+        (vec![], "{\r\n\"a\": 1}", "{\r\n    \"a\": 1\r\n}\r\n"),
+        (vec!["--crlf"], "{\"a\": 1}", "{\r\n    \"a\": 1\r\n}\r\n"),
+        (
+            vec!["--crlf=false"],
+            "{\r\n\"a\": 1}",
+            "{\n    \"a\": 1\n}\n",
+        ),
+    ];
+
+    for (args, input, expected) in cases {
+        let output = run_with_stdin(&args, input);
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), expected);
+    }
+}
+
+#[test]
+fn test_files_from_stdin() {
+    let first = temp_file("files_from_first.json", r#"{"a": 1}"#);
+    let second = temp_file("files_from_second.json", "[1]");
+    let list = format!("{}\n\n{}\n", first.display(), second.display());
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_cli"))
+        .arg("--files-from=-")
+        .stdin(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(list.as_bytes())
+        .unwrap();
+    assert!(child.wait().unwrap().success());
+
+    assert_eq!(fs::read_to_string(&first).unwrap(), "{\n    \"a\": 1\n}\n");
+    assert_eq!(fs::read_to_string(&second).unwrap(), "[\n    1\n]\n");
+}
+
+#[test]
+fn test_verbose_reports_each_file() {
+    let changed = temp_file("verbose_changed.json", r#"{"a": 1}"#);
+    let unchanged = temp_file("verbose_unchanged.json", "[\n    1\n]\n");
+    let list = temp_file(
+        "verbose_list.txt",
+        &format!("{}\n{}\n", changed.display(), unchanged.display()),
+    );
+    let output = run(&["--verbose", "--files-from", list.to_str().unwrap()]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        format!(
+            "formatted {}\nunchanged {}\n",
+            changed.display(),
+            unchanged.display()
+        )
+    );
+
+    let output = run(&[changed.to_str().unwrap()]);
+    assert!(output.stdout.is_empty());
+
+    let unformatted = temp_file("verbose_check.json", r#"{"a": 1}"#);
+    let output = run(&[
+        "--verbose",
+        "--check",
+        unchanged.to_str().unwrap(),
+        unformatted.to_str().unwrap(),
+    ]);
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        format!(
+            "ok {}\nnot formatted {}\n",
+            unchanged.display(),
+            unformatted.display()
+        )
+    );
+
+    let output = run_with_stdin(&["--verbose", "--check"], "[\n    1\n]\n");
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "ok <stdin>\n");
+}
+
+#[test]
+fn test_quiet_suppresses_check_output() {
+    let path = temp_file("quiet_long.json", r#"{"a_long_key": "a long value"}"#);
+    let output = run(&[path.to_str().unwrap(), "--max-line-length", "10"]);
+    assert!(!output.status.success());
+    assert!(!output.stderr.is_empty());
+
+    let output = run(&[path.to_str().unwrap(), "--max-line-length", "10", "--quiet"]);
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn test_dialects() {
+    let cases = vec![
+        (
+            "json",
+            r#"{"a": [1, 2]}"#,
+            Some("{\n    \"a\": [\n        1,\n        2\n    ]\n}\n"),
+        ),
+        ("json", r#"{"a": undefined}"#, None),
+        ("json", "{\"a\": [1, 2]} // note", None),
+        ("json", "{\"a\": [1, 2,]}", None),
+        (
+            "jsonc",
+            "{\"a\": [1, /* one */ 2,],} // note",
+            Some("{\n    \"a\": [\n        1, /* one */\n        2\n    ]\n} // note\n"),
+        ),
+        (
+            "jsonc",
+            "{\n    // editor\n    \"tabSize\": 2, // spaces\n    \"rulers\": [] /* none */\n}\n",
+            Some("{\n    // editor\n    \"tabSize\": 2, // spaces\n    \"rulers\": [] /* none */\n}\n"),
+        ),
+        (
+            "json5",
+            "// config\n{a: 1, /* b */}",
+            Some("// config\n{\n    a: 1, /* b */\n}\n"),
+        ),
+        (
+            "json5",
+            r#"{"a": undefined, 1: "b"}"#,
+            Some("{\n    a: null,\n    '1': 'b',\n}\n"),
+        ),
+        (
+            "json5",
+            r#"{a: [1, -Infinity,], $b: 'x', 'c': "it's"}"#,
+            Some("{\n    a: [\n        1,\n        -Infinity,\n    ],\n    $b: 'x',\n    c: 'it\\'s',\n}\n"),
+        ),
+    ];
+
+    for (index, (dialect, input, expected)) in cases.into_iter().enumerate() {
+        let path = temp_file(&format!("dialect_{}.json", index), input);
+        let output = run(&[path.to_str().unwrap(), &format!("--dialect={}", dialect)]);
+        match expected {
+            Some(expected) => {
+                assert!(output.status.success(), "{}", dialect);
+                assert_eq!(fs::read_to_string(&path).unwrap(), expected);
+            }
+            None => {
+                assert!(!output.status.success(), "{}", dialect);
+                assert_eq!(fs::read_to_string(&path).unwrap(), input);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_reindent_rejects_malformed_input() {
+    let input = "{\n\"a\": [1,\n2]\n}\n";
+    let path = temp_file("reindent.json", input);
+    let output = run(&[path.to_str().unwrap(), "--reindent"]);
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "{\n    \"a\": [1,\n        2]\n}\n"
+    );
+
+    let input = "{\n\"a\": [1,\n2\n}\n";
+    let path = temp_file("reindent_malformed.json", input);
+    let output = run(&[path.to_str().unwrap(), "--reindent"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("reindent_malformed.json:4:1:"));
+    assert_eq!(fs::read_to_string(&path).unwrap(), input);
+}
@@ -1,12 +1,199 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    io::{self, Write},
+    ops::Range,
+};
+
 use json_parser::{
-    node::{Node, SyntaxKind},
-    parse::Parser,
+    escape_string_ascii, escape_string_with_quote,
+    node::{escape_pointer_token, Node, SyntaxKind},
+    parse::{Comment, CommentPlacement, ParseError, ParseOptions, Parser},
+    value::Value,
 };
 
+/// A tree that cannot be formatted, with the JSON pointer of the offending
+/// node.
+#[derive(Debug, PartialEq, Clone)]
+pub enum FormatError {
+    /// A node whose kind is not allowed where it appears, e.g. a property
+    /// assignment inside an array.
+    UnexpectedNode { pointer: String, kind: SyntaxKind },
+    /// An object member that is not a key and a value.
+    MalformedProperty { pointer: String },
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FormatError::UnexpectedNode { pointer, kind } => {
+                write!(f, "Unexpected node at \"{}\": {:?}", pointer, kind)
+            }
+            FormatError::MalformedProperty { pointer } => {
+                write!(f, "Malformed property at \"{}\"", pointer)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ColonSpacing {
+    None,
+    After,
+    /// `"key" :value`
+    Before,
+    /// `"key" : value`
+    Both,
+}
+
+impl ColonSpacing {
+    fn from_sides(before: bool, after: bool) -> Self {
+        match (before, after) {
+            (false, false) => ColonSpacing::None,
+            (false, true) => ColonSpacing::After,
+            (true, false) => ColonSpacing::Before,
+            (true, true) => ColonSpacing::Both,
+        }
+    }
+
+    pub fn space_before(self) -> bool {
+        matches!(self, ColonSpacing::Before | ColonSpacing::Both)
+    }
+
+    pub fn space_after(self) -> bool {
+        matches!(self, ColonSpacing::After | ColonSpacing::Both)
+    }
+}
+
+/// The order object members are written in, applied at every level of
+/// nesting. Sorting is stable, so repeated keys keep their relative order.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SortMode {
+    /// Document order.
+    None,
+    Keys,
+    /// Primitive values first, then arrays, then objects, each group in key
+    /// order.
+    TypeThenKeys,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Dialect {
+    Json,
+    /// Unquoted identifier keys, single-quoted strings and trailing commas.
+    Json5,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+}
+
+impl LineEnding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+        }
+    }
+}
+
+/// Returns the line ending used by most lines of `input`, preferring LF on a
+/// tie or when the input has no line breaks.
+pub fn detect_line_ending(input: &str) -> LineEnding {
+    let crlf = input.matches("\r\n").count();
+    let lf = input.matches('\n').count() - crlf;
+    if crlf > lf {
+        LineEnding::Crlf
+    } else {
+        LineEnding::Lf
+    }
+}
+
+/// How `format_node_with_comments` writes comments.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum CommentStyle {
+    /// As written.
+    Keep,
+    /// As `//` comments, one per line of a block comment.
+    Line,
+    /// As `/* */` comments. Line comments containing `*/` are kept as they
+    /// are.
+    Block,
+}
+
+/// Rendering rules for one class of numbers, applied after the options
+/// covering every number.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct NumberStyle {
+    /// Writes the numbers out in plain decimal digits, as `no_exponential`
+    /// does for every number.
+    pub no_exponential: bool,
+    /// Pads fractions with zeros to at least this many digits, adding the
+    /// decimal point where missing: `1` becomes `1.0` with 1.
+    pub min_fraction_digits: usize,
+}
+
+/// The line width `compact_primitive_arrays` keeps to when `print_width` is
+/// unset.
+const DEFAULT_PRINT_WIDTH: usize = 80;
+
+#[derive(Debug, PartialEq, Clone)]
 pub struct FormatOptions {
     pub spaces: usize,
     pub use_tabs: bool,
     pub trailing_commas: bool,
+    pub colon_spacing: ColonSpacing,
+    pub dialect: Dialect,
+    pub line_ending: LineEnding,
+    pub inline_single_element: bool,
+    /// Rounds every number to this many decimal places. Lossy, off by default.
+    pub round_to: Option<u32>,
+    /// Writes numbers given with an exponent, such as `1e21`, out in plain
+    /// decimal digits. Large or tiny exponents make for very long numbers.
+    pub no_exponential: bool,
+    /// Drops zeros at the end of fractions, `1.50` becoming `1.5` and `2.00`
+    /// becoming `2`, leaving numbers otherwise as written.
+    pub trim_trailing_zeros: bool,
+    /// Keeps objects with at most this many members on one line, as long as
+    /// none of their values needs more than one line and the line fits within
+    /// `print_width`, or 80 columns if that is unset.
+    pub compact_objects_max_members: Option<usize>,
+    /// Lays out arrays of objects sharing the same keys as aligned rows.
+    pub tabular_arrays: bool,
+    /// Pads the inside of single-line objects: `{ "a": 1 }`.
+    pub bracket_spacing: bool,
+    /// Pads the inside of single-line arrays: `[ 1, 2 ]`.
+    pub array_bracket_spacing: bool,
+    /// Packs arrays of scalars several elements per line, filling lines up to
+    /// `print_width`.
+    pub wrap_scalar_arrays: bool,
+    /// The line width the layout options aim to stay within.
+    pub print_width: Option<usize>,
+    pub sort: SortMode,
+    /// Pads the keys of multi-line objects to the longest one, so that the
+    /// colons and values of its members line up.
+    pub align_values: bool,
+    /// Writes characters outside ASCII in strings and keys as `\uXXXX`
+    /// escapes instead of literally.
+    pub ascii_only: bool,
+    /// Keeps arrays holding no objects or arrays on one line when they fit
+    /// within `print_width`, or 80 columns if that is unset.
+    pub compact_primitive_arrays: bool,
+    /// Ends whole documents with a line break. Applies to `format` and
+    /// `push_final_newline`, not to `format_node`.
+    pub final_newline: bool,
+    pub comment_style: CommentStyle,
+    /// JSON pointers of values `format` copies from the input as written,
+    /// leaving them out of the layout options.
+    pub raw_pointers: Vec<String>,
+    /// Applies to numbers written without a fraction or exponent.
+    pub integer_style: NumberStyle,
+    /// Applies to numbers written with a fraction or exponent.
+    pub float_style: NumberStyle,
 }
 
 impl Default for FormatOptions {
@@ -15,20 +202,277 @@ impl Default for FormatOptions {
             spaces: 4,
             use_tabs: false,
             trailing_commas: false,
+            colon_spacing: ColonSpacing::After,
+            dialect: Dialect::Json,
+            line_ending: LineEnding::Lf,
+            inline_single_element: false,
+            round_to: None,
+            no_exponential: false,
+            trim_trailing_zeros: false,
+            compact_objects_max_members: None,
+            tabular_arrays: false,
+            bracket_spacing: false,
+            array_bracket_spacing: false,
+            wrap_scalar_arrays: false,
+            print_width: None,
+            sort: SortMode::None,
+            compact_primitive_arrays: false,
+            ascii_only: false,
+            align_values: false,
+            final_newline: true,
+            comment_style: CommentStyle::Keep,
+            raw_pointers: Vec::new(),
+            integer_style: NumberStyle::default(),
+            float_style: NumberStyle::default(),
+        }
+    }
+}
+
+impl FormatOptions {
+    pub fn builder() -> FormatOptionsBuilder {
+        FormatOptionsBuilder::default()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct FormatOptionsBuilder {
+    options: FormatOptions,
+}
+
+impl FormatOptionsBuilder {
+    pub fn spaces(mut self, spaces: usize) -> Self {
+        self.options.spaces = spaces;
+        self
+    }
+
+    pub fn use_tabs(mut self, use_tabs: bool) -> Self {
+        self.options.use_tabs = use_tabs;
+        self
+    }
+
+    pub fn trailing_commas(mut self, trailing_commas: bool) -> Self {
+        self.options.trailing_commas = trailing_commas;
+        self
+    }
+
+    pub fn colon_spacing(mut self, colon_spacing: ColonSpacing) -> Self {
+        self.options.colon_spacing = colon_spacing;
+        self
+    }
+
+    /// Sets the space before the colon, keeping the one after as it is.
+    pub fn space_before_colon(mut self, space: bool) -> Self {
+        let after = self.options.colon_spacing.space_after();
+        self.options.colon_spacing = ColonSpacing::from_sides(space, after);
+        self
+    }
+
+    /// Sets the space after the colon, keeping the one before as it is.
+    pub fn space_after_colon(mut self, space: bool) -> Self {
+        let before = self.options.colon_spacing.space_before();
+        self.options.colon_spacing = ColonSpacing::from_sides(before, space);
+        self
+    }
+
+    pub fn dialect(mut self, dialect: Dialect) -> Self {
+        self.options.dialect = dialect;
+        self
+    }
+
+    pub fn line_ending(mut self, line_ending: LineEnding) -> Self {
+        self.options.line_ending = line_ending;
+        self
+    }
+
+    pub fn inline_single_element(mut self, inline_single_element: bool) -> Self {
+        self.options.inline_single_element = inline_single_element;
+        self
+    }
+
+    pub fn round_to(mut self, places: u32) -> Self {
+        self.options.round_to = Some(places);
+        self
+    }
+
+    pub fn no_exponential(mut self, no_exponential: bool) -> Self {
+        self.options.no_exponential = no_exponential;
+        self
+    }
+
+    pub fn trim_trailing_zeros(mut self, trim_trailing_zeros: bool) -> Self {
+        self.options.trim_trailing_zeros = trim_trailing_zeros;
+        self
+    }
+
+    pub fn compact_objects_max_members(mut self, max_members: usize) -> Self {
+        self.options.compact_objects_max_members = Some(max_members);
+        self
+    }
+
+    pub fn tabular_arrays(mut self, tabular_arrays: bool) -> Self {
+        self.options.tabular_arrays = tabular_arrays;
+        self
+    }
+
+    pub fn bracket_spacing(mut self, bracket_spacing: bool) -> Self {
+        self.options.bracket_spacing = bracket_spacing;
+        self
+    }
+
+    pub fn array_bracket_spacing(mut self, array_bracket_spacing: bool) -> Self {
+        self.options.array_bracket_spacing = array_bracket_spacing;
+        self
+    }
+
+    pub fn wrap_scalar_arrays(mut self, wrap_scalar_arrays: bool) -> Self {
+        self.options.wrap_scalar_arrays = wrap_scalar_arrays;
+        self
+    }
+
+    pub fn print_width(mut self, print_width: usize) -> Self {
+        self.options.print_width = Some(print_width);
+        self
+    }
+
+    pub fn sort(mut self, sort: SortMode) -> Self {
+        self.options.sort = sort;
+        self
+    }
+
+    /// Shorthand for `sort(SortMode::Keys)`, or `SortMode::None` when false.
+    pub fn sort_keys(mut self, sort_keys: bool) -> Self {
+        self.options.sort = if sort_keys {
+            SortMode::Keys
+        } else {
+            SortMode::None
+        };
+        self
+    }
+
+    pub fn compact_primitive_arrays(mut self, compact_primitive_arrays: bool) -> Self {
+        self.options.compact_primitive_arrays = compact_primitive_arrays;
+        self
+    }
+
+    pub fn align_values(mut self, align_values: bool) -> Self {
+        self.options.align_values = align_values;
+        self
+    }
+
+    pub fn ascii_only(mut self, ascii_only: bool) -> Self {
+        self.options.ascii_only = ascii_only;
+        self
+    }
+
+    pub fn final_newline(mut self, final_newline: bool) -> Self {
+        self.options.final_newline = final_newline;
+        self
+    }
+
+    pub fn comment_style(mut self, comment_style: CommentStyle) -> Self {
+        self.options.comment_style = comment_style;
+        self
+    }
+
+    pub fn raw_pointers(mut self, raw_pointers: Vec<String>) -> Self {
+        self.options.raw_pointers = raw_pointers;
+        self
+    }
+
+    pub fn integer_style(mut self, integer_style: NumberStyle) -> Self {
+        self.options.integer_style = integer_style;
+        self
+    }
+
+    pub fn float_style(mut self, float_style: NumberStyle) -> Self {
+        self.options.float_style = float_style;
+        self
+    }
+
+    pub fn build(self) -> FormatOptions {
+        self.options
+    }
+}
+
+/// Where the value at `pointer` is in the input and in the formatted output,
+/// as byte ranges.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SourceMapping {
+    pub pointer: String,
+    pub input: Range<usize>,
+    pub output: Range<usize>,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct Progress {
+    pub lines: usize,
+    pub bytes: usize,
+}
+
+/// Hands output to a writer, reporting the running `Progress` after each
+/// piece of a line.
+struct ProgressWriter<W, F> {
+    writer: W,
+    on_progress: F,
+    progress: Progress,
+    /// Whether the next byte written starts a new line.
+    at_line_start: bool,
+}
+
+impl<W: Write, F: FnMut(Progress)> ProgressWriter<W, F> {
+    fn write(&mut self, text: &str) -> io::Result<()> {
+        for piece in text.split_inclusive('\n') {
+            self.writer.write_all(piece.as_bytes())?;
+            if self.at_line_start {
+                self.progress.lines += 1;
+            }
+            self.at_line_start = piece.ends_with('\n');
+            self.progress.bytes += piece.len();
+            (self.on_progress)(self.progress);
         }
+        Ok(())
     }
 }
 
 pub struct Formatter {
     indent: usize,
+    /// Width of what precedes the node being formatted on its line, past the
+    /// indentation, e.g. the key of a property.
+    column: usize,
     options: FormatOptions,
+    /// Comments left to write, by the pointer of the value they belong to.
+    comments: HashMap<String, Vec<(CommentPlacement, String)>>,
+    /// Source text of the `raw_pointers` values left to write, last first
+    /// for repeated keys.
+    raw: HashMap<String, Vec<String>>,
+    /// Pointer of the node being formatted, tracked only while there are
+    /// comments or raw values left.
+    pointer: String,
+}
+
+/// Comment lines to write around one line of a multi-line object or array.
+#[derive(Debug, Default)]
+struct LineComments {
+    above: Vec<String>,
+    end_of_line: Vec<String>,
+    below: Vec<String>,
+}
+
+impl LineComments {
+    fn is_empty(&self) -> bool {
+        self.above.is_empty() && self.end_of_line.is_empty() && self.below.is_empty()
+    }
 }
 
 impl Formatter {
     pub fn new(_options: Option<FormatOptions>) -> Self {
         Formatter {
             indent: 0,
+            column: 0,
             options: _options.unwrap_or_default(),
+            comments: HashMap::new(),
+            raw: HashMap::new(),
+            pointer: String::new(),
         }
     }
 
@@ -54,12 +498,62 @@ impl Formatter {
         s
     }
 
+    fn push_newline(&self, s: &mut String) {
+        s.push_str(self.options.line_ending.as_str());
+    }
+
+    fn use_trailing_commas(&self) -> bool {
+        self.options.trailing_commas || self.options.dialect == Dialect::Json5
+    }
+
     fn format_primitive(&self, node: &Node) -> String {
         match &node.kind {
+            SyntaxKind::Identifier(text)
+                if self.options.dialect == Dialect::Json5 && is_identifier_name(text) =>
+            {
+                text.clone()
+            }
             SyntaxKind::StringLiteral(text) | SyntaxKind::Identifier(text) => {
-                format!("\"{}\"", text)
+                let quote = match self.options.dialect {
+                    Dialect::Json => '"',
+                    Dialect::Json5 => '\'',
+                };
+                if self.options.ascii_only {
+                    escape_string_ascii(text, quote)
+                } else {
+                    escape_string_with_quote(text, quote)
+                }
+            }
+            // JSON has no spelling for these, so they degrade to null as in
+            // JSON.stringify.
+            SyntaxKind::NumberLiteral(number) if number.is_non_finite() => {
+                match self.options.dialect {
+                    Dialect::Json => "null".to_string(),
+                    Dialect::Json5 => number.lexeme.clone(),
+                }
             }
-            SyntaxKind::NumberLiteral(value) => value.to_string(),
+            // Numbers keep the text they were parsed from. Rounding goes through
+            // f64's Display, which never switches to exponent notation. Integers
+            // have nothing to round off and would lose digits past 2^53.
+            SyntaxKind::NumberLiteral(number) => match self.options.round_to {
+                Some(places) if !number.is_integer() => {
+                    self.number_text(round(number.value, places).to_string(), false)
+                }
+                _ => self.number_text(number.lexeme.clone(), number.is_integer()),
+            },
+            #[cfg(feature = "decimal")]
+            SyntaxKind::DecimalLiteral(value) => match self.options.round_to {
+                Some(places) => self.number_text(
+                    value
+                        .round_dp_with_strategy(
+                            places,
+                            json_parser::rust_decimal::RoundingStrategy::MidpointAwayFromZero,
+                        )
+                        .to_string(),
+                    value.scale() == 0,
+                ),
+                None => self.number_text(value.to_string(), value.scale() == 0),
+            },
             SyntaxKind::TrueKeyword => "true".to_string(),
             SyntaxKind::FalseKeyword => "false".to_string(),
             SyntaxKind::NullKeyword => "null".to_string(),
@@ -67,118 +561,1357 @@ impl Formatter {
         }
     }
 
-    fn format_array(&mut self, node: &Node) -> String {
-        let mut s = String::new();
-        s.push('[');
-        self.up_indent();
-        let mut first = true;
-        for child in &node.children {
-            if first {
-                first = false;
+    /// Applies `no_exponential`, `trim_trailing_zeros` and then the integer
+    /// or float style to the text of a number.
+    fn number_text(&self, mut text: String, is_integer: bool) -> String {
+        if self.options.no_exponential {
+            text = expand_exponent(&text);
+        }
+        if self.options.trim_trailing_zeros {
+            text = trim_fraction_zeros(&text);
+        }
+        let style = if is_integer {
+            self.options.integer_style
+        } else {
+            self.options.float_style
+        };
+        if style.no_exponential {
+            text = expand_exponent(&text);
+        }
+        pad_fraction(&text, style.min_fraction_digits)
+    }
+
+    /// Returns the children of `node` in output order, sorting object members
+    /// as `sort` asks.
+    fn members<'a>(&self, node: &'a Node) -> Vec<&'a Node> {
+        let mut members: Vec<&Node> = node.children.iter().collect();
+        if node.kind != SyntaxKind::ObjectLiteralExpression {
+            return members;
+        }
+        match self.options.sort {
+            SortMode::None => {}
+            SortMode::Keys => members.sort_by(|a, b| property_key(a).cmp(property_key(b))),
+            SortMode::TypeThenKeys => members.sort_by(|a, b| {
+                (value_rank(a), property_key(a)).cmp(&(value_rank(b), property_key(b)))
+            }),
+        }
+        members
+    }
+
+    /// Joins already formatted children on a single line, or returns None if
+    /// any of them spans more than one line.
+    fn format_inline(&self, children: &[String], open: char, close: char) -> Option<String> {
+        if children.iter().any(|child| child.contains('\n')) {
+            return None;
+        }
+        if children.is_empty() {
+            return Some(format!("{}{}", open, close));
+        }
+        let padding = if self.bracket_spacing(open) { " " } else { "" };
+        Some(format!(
+            "{}{}{}{}{}",
+            open,
+            padding,
+            children.join(", "),
+            padding,
+            close
+        ))
+    }
+
+    fn bracket_spacing(&self, open: char) -> bool {
+        match open {
+            '{' => self.options.bracket_spacing,
+            _ => self.options.array_bracket_spacing,
+        }
+    }
+
+    /// Puts the children on a single line if that fits within `print_width`,
+    /// counting the indentation, the `column` it starts at and a trailing
+    /// comma.
+    fn format_fitting(
+        &self,
+        children: &[String],
+        open: char,
+        close: char,
+        column: usize,
+    ) -> Option<String> {
+        let width = self.options.print_width?;
+        let inline = self.format_inline(children, open, close)?;
+        self.fits(&inline, column, width).then_some(inline)
+    }
+
+    fn fits(&self, line: &str, column: usize, width: usize) -> bool {
+        self.indent_string().chars().count() + column + line.chars().count() < width
+    }
+
+    /// Keeps an array of scalars on one line when `compact_primitive_arrays`
+    /// is set and it fits within `print_width`, or 80 columns without one.
+    fn format_compact_primitives(
+        &self,
+        node: &Node,
+        elements: &[String],
+        column: usize,
+    ) -> Option<String> {
+        if !self.options.compact_primitive_arrays || !node.children.iter().all(is_scalar) {
+            return None;
+        }
+        let width = self.options.print_width.unwrap_or(DEFAULT_PRINT_WIDTH);
+        let inline = self.format_inline(elements, '[', ']')?;
+        self.fits(&inline, column, width).then_some(inline)
+    }
+
+    fn format_inline_single_element(
+        &self,
+        children: &[String],
+        open: char,
+        close: char,
+    ) -> Option<String> {
+        if !self.options.inline_single_element || children.len() != 1 {
+            return None;
+        }
+        self.format_inline(children, open, close)
+    }
+
+    /// Groups the elements of an all-scalar array into lines that fit within
+    /// `print_width`, counting the indentation and the trailing comma. Expects
+    /// the indentation to already be at the level of the elements.
+    fn format_wrapped_scalars(&mut self, node: &Node) -> Option<Vec<String>> {
+        let width = self.options.print_width?;
+        if !self.options.wrap_scalar_arrays || node.children.is_empty() {
+            return None;
+        }
+        if !node.children.iter().all(is_scalar) {
+            return None;
+        }
+
+        let indent_width = self.indent_string().chars().count();
+        let mut lines: Vec<String> = Vec::new();
+        let mut line = String::new();
+        for element in &node.children {
+            let element = self.format_primitive(element);
+            // the joining ", " plus the comma that ends the line
+            let joined_width =
+                indent_width + line.chars().count() + 2 + element.chars().count() + 1;
+            if line.is_empty() {
+                line = element;
+            } else if joined_width <= width {
+                line.push_str(", ");
+                line.push_str(&element);
             } else {
-                s.push(',');
+                lines.push(std::mem::replace(&mut line, element));
             }
-            s.push('\n');
-            s.push_str(&self.indent_string());
-            s.push_str(&self.format_node(child));
         }
-        if self.options.trailing_commas {
-            s.push(',');
+        lines.push(line);
+        Some(lines)
+    }
+
+    /// Formats the members of every element when `node` is an array of
+    /// objects sharing the same keys, the candidates for `tabular_arrays`.
+    fn format_table_properties(&mut self, node: &Node) -> Option<Vec<Vec<(String, String)>>> {
+        if !self.options.tabular_arrays || node.children.len() < 2 {
+            return None;
         }
-        self.down_indent();
-        s.push('\n');
-        s.push_str(&self.indent_string());
-        s.push(']');
-        s
+        // rows have no room for comments, nor for values kept as written
+        if self.has_attachments_within() {
+            return None;
+        }
+        let keys = |element: &Node| -> Vec<String> {
+            self.members(element)
+                .into_iter()
+                .map(|property| property_key(property).to_string())
+                .collect()
+        };
+        let columns = keys(&node.children[0]);
+        let is_uniform = node.children.iter().all(|element| {
+            element.kind == SyntaxKind::ObjectLiteralExpression && keys(element) == columns
+        });
+        if !is_uniform || columns.is_empty() {
+            return None;
+        }
+        Some(
+            node.children
+                .iter()
+                .enumerate()
+                .map(|(index, element)| {
+                    self.with_pointer(&index.to_string(), |formatter| {
+                        formatter.format_properties(element)
+                    })
+                })
+                .collect(),
+        )
     }
 
-    fn format_object(&mut self, node: &Node) -> String {
+    /// Renders each element of an array of uniform objects as a single-line
+    /// row, padding members so that they line up as columns.
+    fn format_table_rows(&self, table: &[Vec<(String, String)>]) -> Option<Vec<String>> {
+        let mut cells = Vec::new();
+        for properties in table {
+            let row: Vec<String> = properties
+                .iter()
+                .map(|(key, value)| self.property(key, 0, value))
+                .collect();
+            if row.iter().any(|cell| cell.contains('\n')) {
+                return None;
+            }
+            cells.push(row);
+        }
+
+        let columns = cells[0].len();
+        let widths: Vec<usize> = (0..columns)
+            .map(|column| {
+                cells
+                    .iter()
+                    .map(|row| row[column].chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let rows = cells
+            .into_iter()
+            .map(|row| {
+                let padding = if self.bracket_spacing('{') { " " } else { "" };
+                let mut s = String::from("{");
+                s.push_str(padding);
+                let last = row.len() - 1;
+                for (column, cell) in row.into_iter().enumerate() {
+                    if column == last {
+                        s.push_str(&cell);
+                        break;
+                    }
+                    let width_padding = widths[column] - cell.chars().count();
+                    s.push_str(&cell);
+                    s.push(',');
+                    s.push_str(&" ".repeat(width_padding + 1));
+                }
+                s.push_str(padding);
+                s.push('}');
+                s
+            })
+            .collect();
+        Some(rows)
+    }
+
+    /// Writes `lines` one per line between `open` and `close`, a level deeper
+    /// than the brackets.
+    fn format_lines(&mut self, lines: &[String], open: char, close: char) -> String {
+        self.format_commented_lines(lines, &[], &[], open, close)
+    }
+
+    /// Like `format_lines`, with the comments of each line around it and the
+    /// `inside` comments after the last.
+    fn format_commented_lines(
+        &mut self,
+        lines: &[String],
+        comments: &[LineComments],
+        inside: &[String],
+        open: char,
+        close: char,
+    ) -> String {
         let mut s = String::new();
-        s.push('{');
+        s.push(open);
         self.up_indent();
-        let mut first = true;
-        for child in &node.children {
-            if first {
-                first = false;
-            } else {
-                s.push(',');
+        for (index, line) in lines.iter().enumerate() {
+            let comments = comments.get(index);
+            for text in comments.iter().flat_map(|comments| &comments.above) {
+                self.push_newline(&mut s);
+                s.push_str(&self.indent_string());
+                s.push_str(text);
             }
-            s.push('\n');
+            self.push_newline(&mut s);
             s.push_str(&self.indent_string());
-            s.push_str(&self.format_node(child));
+            s.push_str(line);
+            if index + 1 < lines.len() || self.use_trailing_commas() {
+                s.push(',');
+            }
+            if let Some(comments) = comments {
+                for text in &comments.end_of_line {
+                    s.push(' ');
+                    s.push_str(text);
+                }
+                for text in &comments.below {
+                    self.push_newline(&mut s);
+                    s.push_str(&self.indent_string());
+                    s.push_str(text);
+                }
+            }
         }
-        if self.options.trailing_commas {
-            s.push(',');
+        for text in inside {
+            self.push_newline(&mut s);
+            s.push_str(&self.indent_string());
+            s.push_str(text);
         }
         self.down_indent();
-        s.push('\n');
+        self.push_newline(&mut s);
         s.push_str(&self.indent_string());
-        s.push('}');
+        s.push(close);
         s
     }
 
-    fn format_node(&mut self, node: &Node) -> String {
-        match &node.kind {
-            SyntaxKind::ObjectLiteralExpression => self.format_object(node),
-            SyntaxKind::ArrayLiteralExpression => self.format_array(node),
-            SyntaxKind::StringLiteral(_)
-            | SyntaxKind::NumberLiteral(_)
-            | SyntaxKind::Identifier(_)
-            | SyntaxKind::TrueKeyword
-            | SyntaxKind::FalseKeyword
-            | SyntaxKind::NullKeyword => self.format_primitive(node),
-            SyntaxKind::PropertyAssignment => {
-                let mut s = String::new();
-                s.push_str(&self.format_node(&node.children[0]));
-                s.push(':');
-                s.push(' ');
-                s.push_str(&self.format_node(&node.children[1]));
-                s
+    // Every layout below is chosen from children formatted once, at the
+    // indentation they get on lines of their own. Formatting them again for
+    // each layout tried would double the work at every level of nesting.
+
+    fn format_array(&mut self, node: &Node) -> String {
+        let column = std::mem::take(&mut self.column);
+        self.up_indent();
+        let table = self.format_table_properties(node);
+        let elements: Vec<String> = match &table {
+            Some(table) => node
+                .children
+                .iter()
+                .zip(table)
+                .enumerate()
+                .map(|(index, (element, properties))| {
+                    self.with_pointer(&index.to_string(), |formatter| {
+                        formatter.layout_object(element, properties, 0)
+                    })
+                })
+                .collect(),
+            None => node
+                .children
+                .iter()
+                .enumerate()
+                .map(|(index, element)| {
+                    self.with_pointer(&index.to_string(), |formatter| {
+                        formatter.format_node(element)
+                    })
+                })
+                .collect(),
+        };
+        self.down_indent();
+
+        if !self.comments.is_empty() {
+            let comments: Vec<LineComments> = (0..elements.len())
+                .map(|index| self.take_line_comments(&index.to_string()))
+                .collect();
+            let inside = self.take_inside_comments();
+            if !inside.is_empty() || comments.iter().any(|comments| !comments.is_empty()) {
+                return self.format_commented_lines(&elements, &comments, &inside, '[', ']');
             }
-            _ => unreachable!("format called on non-format node, {:?}", node),
+        }
+
+        if let Some(inline) = self.format_inline_single_element(&elements, '[', ']') {
+            return inline;
+        }
+        if let Some(inline) = self.format_fitting(&elements, '[', ']', column) {
+            return inline;
+        }
+        if let Some(inline) = self.format_compact_primitives(node, &elements, column) {
+            return inline;
+        }
+        if let Some(rows) = table.and_then(|table| self.format_table_rows(&table)) {
+            return self.format_lines(&rows, '[', ']');
+        }
+        self.up_indent();
+        let wrapped = self.format_wrapped_scalars(node);
+        self.down_indent();
+        match wrapped {
+            Some(lines) => self.format_lines(&lines, '[', ']'),
+            None => self.format_lines(&elements, '[', ']'),
         }
     }
 
-    pub fn format(&mut self, input: &str) -> String {
-        let mut parser = Parser::new(input);
-        let node = parser.parse();
-        self.format_node(&node)
+    fn format_object(&mut self, node: &Node) -> String {
+        let column = std::mem::take(&mut self.column);
+        let properties = self.format_properties(node);
+        self.layout_object(node, &properties, column)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use json_parser::node::SyntaxKind;
+    /// Formats the keys and values of an object's members in output order,
+    /// at the indentation they get on lines of their own.
+    fn format_properties(&mut self, node: &Node) -> Vec<(String, String)> {
+        let members = self.members(node);
+        let keys: Vec<String> = members
+            .iter()
+            .map(|property| self.format_primitive(&property.children[0]))
+            .collect();
+        let key_width = self.key_width(&keys);
+        self.up_indent();
+        let properties = members
+            .into_iter()
+            .zip(keys)
+            .map(|(property, key)| {
+                let padding = key_width.saturating_sub(key.chars().count());
+                self.column = self.property(&key, padding, "").chars().count();
+                let value = self.with_pointer(property_key(property), |formatter| {
+                    formatter.format_node(&property.children[1])
+                });
+                self.column = 0;
+                (key, value)
+            })
+            .collect();
+        self.down_indent();
+        properties
+    }
 
-    use super::*;
+    /// The width keys are padded to for `align_values`, or 0 without it.
+    fn key_width<S: AsRef<str>>(&self, keys: &[S]) -> usize {
+        if !self.options.align_values {
+            return 0;
+        }
+        keys.iter()
+            .map(|key| key.as_ref().chars().count())
+            .max()
+            .unwrap_or(0)
+    }
 
-    #[test]
-    fn test_indent_string() {
-        let cases = vec![
-            (None, "    "),
-            (
-                Some(FormatOptions {
-                    spaces: 2,
-                    ..Default::default()
-                }),
-                "  ",
-            ),
-            (
-                Some(FormatOptions {
-                    use_tabs: true,
-                    ..Default::default()
-                }),
-                "\t",
-            ),
-            (
-                Some(FormatOptions {
-                    spaces: 2,
-                    use_tabs: true,
-                    ..Default::default()
-                }),
-                "\t",
-            ),
-        ];
+    fn layout_object(
+        &mut self,
+        node: &Node,
+        properties: &[(String, String)],
+        column: usize,
+    ) -> String {
+        if !self.comments.is_empty() {
+            let comments: Vec<LineComments> = self
+                .members(node)
+                .into_iter()
+                .map(|property| self.take_line_comments(property_key(property)))
+                .collect();
+            let inside = self.take_inside_comments();
+            if !inside.is_empty() || comments.iter().any(|comments| !comments.is_empty()) {
+                let lines = self.object_lines(properties);
+                return self.format_commented_lines(&lines, &comments, &inside, '{', '}');
+            }
+        }
+
+        let members: Vec<String> = properties
+            .iter()
+            .map(|(key, value)| self.property(key, 0, value))
+            .collect();
+        if let Some(inline) = self.format_inline_single_element(&members, '{', '}') {
+            return inline;
+        }
+        if let Some(inline) = self.format_fitting(&members, '{', '}', column) {
+            return inline;
+        }
+        if let Some(max_members) = self.options.compact_objects_max_members {
+            if node.children.len() <= max_members {
+                let width = self.options.print_width.unwrap_or(DEFAULT_PRINT_WIDTH);
+                let inline = self.format_inline(&members, '{', '}');
+                if let Some(inline) = inline.filter(|inline| self.fits(inline, column, width)) {
+                    return inline;
+                }
+            }
+        }
+        let lines = self.object_lines(properties);
+        self.format_lines(&lines, '{', '}')
+    }
+
+    /// The members of a multi-line object, one per line.
+    fn object_lines(&self, properties: &[(String, String)]) -> Vec<String> {
+        let keys: Vec<&str> = properties.iter().map(|(key, _)| key.as_str()).collect();
+        let key_width = self.key_width(&keys);
+        properties
+            .iter()
+            .map(|(key, value)| {
+                let padding = key_width.saturating_sub(key.chars().count());
+                self.property(key, padding, value)
+            })
+            .collect()
+    }
+
+    /// Runs `f` with `token` appended to the pointer of the node being
+    /// formatted.
+    fn with_pointer<T>(&mut self, token: &str, f: impl FnOnce(&mut Self) -> T) -> T {
+        if self.comments.is_empty() && self.raw.is_empty() {
+            return f(self);
+        }
+        let len = self.pointer.len();
+        self.pointer.push('/');
+        self.pointer.push_str(&escape_pointer_token(token));
+        let result = f(self);
+        self.pointer.truncate(len);
+        result
+    }
+
+    fn take_raw(&mut self) -> Option<String> {
+        if self.raw.is_empty() {
+            return None;
+        }
+        let texts = self.raw.get_mut(&self.pointer)?;
+        let raw = texts.pop();
+        if texts.is_empty() {
+            self.raw.remove(&self.pointer);
+        }
+        raw
+    }
+
+    /// Whether comments or raw values are left for the node being formatted
+    /// or anything in it.
+    fn has_attachments_within(&self) -> bool {
+        self.comments.keys().chain(self.raw.keys()).any(|pointer| {
+            pointer
+                .strip_prefix(self.pointer.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        })
+    }
+
+    fn has_inside_comments(&self) -> bool {
+        self.comments.get(&self.pointer).is_some_and(|comments| {
+            comments
+                .iter()
+                .any(|(placement, _)| *placement == CommentPlacement::Inside)
+        })
+    }
+
+    /// Removes the comments at `pointer` placed as `keep` selects.
+    fn take_comments(
+        &mut self,
+        pointer: &str,
+        keep: impl Fn(CommentPlacement) -> bool,
+    ) -> Vec<(CommentPlacement, String)> {
+        let Some(comments) = self.comments.get_mut(pointer) else {
+            return Vec::new();
+        };
+        let (taken, left) = std::mem::take(comments)
+            .into_iter()
+            .partition(|(placement, _)| keep(*placement));
+        *comments = left;
+        if comments.is_empty() {
+            self.comments.remove(pointer);
+        }
+        taken
+    }
+
+    fn take_inside_comments(&mut self) -> Vec<String> {
+        let pointer = self.pointer.clone();
+        self.take_comments(&pointer, |placement| placement == CommentPlacement::Inside)
+            .into_iter()
+            .flat_map(|(_, text)| self.comment_lines(&text))
+            .collect()
+    }
+
+    /// Removes the comments around the child of the node being formatted
+    /// found at `token`.
+    fn take_line_comments(&mut self, token: &str) -> LineComments {
+        let pointer = format!("{}/{}", self.pointer, escape_pointer_token(token));
+        self.take_around_comments(&pointer)
+    }
+
+    fn take_around_comments(&mut self, pointer: &str) -> LineComments {
+        let mut comments = LineComments::default();
+        for (placement, text) in
+            self.take_comments(pointer, |placement| placement != CommentPlacement::Inside)
+        {
+            for line in self.comment_lines(&text) {
+                match placement {
+                    CommentPlacement::Above => comments.above.push(line),
+                    // nothing can follow a line comment on its line
+                    CommentPlacement::EndOfLine
+                        if !comments
+                            .end_of_line
+                            .last()
+                            .is_some_and(|text| text.starts_with("//")) =>
+                    {
+                        comments.end_of_line.push(line)
+                    }
+                    _ => comments.below.push(line),
+                }
+            }
+        }
+        comments
+    }
+
+    /// `text` rewritten in `comment_style`, as the comments to write on
+    /// lines of their own.
+    fn comment_lines(&self, text: &str) -> Vec<String> {
+        match self.options.comment_style {
+            CommentStyle::Line => {
+                let Some(body) = text.strip_prefix("/*") else {
+                    return vec![text.to_string()];
+                };
+                let body = body.strip_suffix("*/").unwrap_or(body);
+                let lines: Vec<&str> = body
+                    .lines()
+                    .map(|line| {
+                        let line = line.trim();
+                        line.strip_prefix('*').map_or(line, str::trim_start)
+                    })
+                    .collect();
+                let start = lines.iter().position(|line| !line.is_empty());
+                let end = lines.iter().rposition(|line| !line.is_empty());
+                match start.zip(end) {
+                    Some((start, end)) => lines[start..=end]
+                        .iter()
+                        .map(|line| format!("// {line}").trim_end().to_string())
+                        .collect(),
+                    None => vec!["//".to_string()],
+                }
+            }
+            CommentStyle::Block => match text.strip_prefix("//") {
+                Some(body) if !body.contains("*/") => {
+                    let body = body.trim();
+                    if body.is_empty() {
+                        vec!["/* */".to_string()]
+                    } else {
+                        vec![format!("/* {body} */")]
+                    }
+                }
+                _ => vec![text.to_string()],
+            },
+            CommentStyle::Keep => vec![text.to_string()],
+        }
+    }
+
+    /// A member written as `key`, `padding` spaces, the colon and `value`.
+    fn property(&self, key: &str, padding: usize, value: &str) -> String {
+        let mut s = String::from(key);
+        s.push_str(&" ".repeat(padding));
+        if self.options.colon_spacing.space_before() {
+            s.push(' ');
+        }
+        s.push(':');
+        if self.options.colon_spacing.space_after() {
+            s.push(' ');
+        }
+        s.push_str(value);
+        s
+    }
+
+    /// Panics on trees the parser would never produce; use `try_format_node`
+    /// for trees built or edited by hand.
+    pub fn format_node(&mut self, node: &Node) -> String {
+        if let Some(raw) = self.take_raw() {
+            return raw;
+        }
+        match &node.kind {
+            // empty containers stay on one line whatever the layout options,
+            // unless they hold comments
+            SyntaxKind::ObjectLiteralExpression
+                if node.children.is_empty() && !self.has_inside_comments() =>
+            {
+                "{}".to_string()
+            }
+            SyntaxKind::ArrayLiteralExpression
+                if node.children.is_empty() && !self.has_inside_comments() =>
+            {
+                "[]".to_string()
+            }
+            SyntaxKind::ObjectLiteralExpression => self.format_object(node),
+            SyntaxKind::ArrayLiteralExpression => self.format_array(node),
+            SyntaxKind::StringLiteral(_)
+            | SyntaxKind::NumberLiteral(_)
+            | SyntaxKind::Identifier(_)
+            | SyntaxKind::TrueKeyword
+            | SyntaxKind::FalseKeyword
+            | SyntaxKind::NullKeyword => self.format_primitive(node),
+            #[cfg(feature = "decimal")]
+            SyntaxKind::DecimalLiteral(_) => self.format_primitive(node),
+            SyntaxKind::PropertyAssignment => {
+                let key = self.format_node(&node.children[0]);
+                self.column = self.property(&key, 0, "").chars().count();
+                let value = self.format_node(&node.children[1]);
+                self.column = 0;
+                self.property(&key, 0, &value)
+            }
+        }
+    }
+
+    /// Like `format_node`, but checks the tree first and returns an error
+    /// instead of panicking on nodes built in the wrong shape.
+    pub fn try_format_node(&mut self, node: &Node) -> Result<String, FormatError> {
+        check_tree(node)?;
+        Ok(self.format_node(node))
+    }
+
+    /// Like `format_node`, but writes `comments`, as found by
+    /// `Parser::comments`, next to the values they belong to in
+    /// `comment_style`. Objects and arrays holding comments are always written
+    /// one member per line.
+    pub fn format_node_with_comments(&mut self, node: &Node, comments: &[Comment]) -> String {
+        self.pointer.clear();
+        for comment in comments {
+            self.comments
+                .entry(comment.pointer.clone())
+                .or_default()
+                .push((comment.placement, comment.text.clone()));
+        }
+        let root = self.take_around_comments("");
+        let mut s = String::new();
+        for text in &root.above {
+            s.push_str(text);
+            self.push_newline(&mut s);
+        }
+        s.push_str(&self.format_node(node));
+        for text in &root.end_of_line {
+            s.push(' ');
+            s.push_str(text);
+        }
+        for text in &root.below {
+            self.push_newline(&mut s);
+            s.push_str(text);
+        }
+        self.comments.clear();
+        s
+    }
+
+    pub fn format_to<W: Write>(&mut self, node: &Node, writer: W) -> io::Result<()> {
+        self.format_to_with_progress(node, writer, |_| {})
+    }
+
+    /// Formats `node` to `writer`, calling `on_progress` with the running line
+    /// and byte counts after each line written. A root container laid out one
+    /// child per line is written a child at a time as each is formatted, so
+    /// only one child's output is held at once; the other layouts depend on
+    /// all of the children and are formatted whole before writing.
+    pub fn format_to_with_progress<W: Write, F: FnMut(Progress)>(
+        &mut self,
+        node: &Node,
+        writer: W,
+        on_progress: F,
+    ) -> io::Result<()> {
+        let mut output = ProgressWriter {
+            writer,
+            on_progress,
+            progress: Progress::default(),
+            at_line_start: true,
+        };
+        if self.streams_children(node) {
+            self.stream_children(node, &mut output)?;
+        } else {
+            let formatted = self.format_node(node);
+            output.write(&formatted)?;
+        }
+        output.writer.flush()
+    }
+
+    /// Whether `node` is laid out one child per line whenever it does not
+    /// fit on one, which is what `stream_children` writes.
+    fn streams_children(&self, node: &Node) -> bool {
+        if node.children.len() < 2 || !self.comments.is_empty() || !self.raw.is_empty() {
+            return false;
+        }
+        match node.kind {
+            SyntaxKind::ArrayLiteralExpression => {
+                let packs_scalars = (self.options.compact_primitive_arrays
+                    || self.options.wrap_scalar_arrays)
+                    && node.children.iter().all(is_scalar);
+                !(self.options.tabular_arrays || packs_scalars)
+            }
+            SyntaxKind::ObjectLiteralExpression => self
+                .options
+                .compact_objects_max_members
+                .is_none_or(|max_members| node.children.len() > max_members),
+            _ => false,
+        }
+    }
+
+    /// Writes the lines `format_lines` would give `node`, formatting each
+    /// child as the one before it is written. Children are held back only
+    /// while the container might still fit on one line under `print_width`,
+    /// and if it does it is formatted whole instead.
+    fn stream_children<W: Write, F: FnMut(Progress)>(
+        &mut self,
+        node: &Node,
+        output: &mut ProgressWriter<W, F>,
+    ) -> io::Result<()> {
+        let (open, close) = match node.kind {
+            SyntaxKind::ObjectLiteralExpression => ('{', '}'),
+            _ => ('[', ']'),
+        };
+        let members = self.members(node);
+        let keys: Vec<String> = match node.kind {
+            SyntaxKind::ObjectLiteralExpression => members
+                .iter()
+                .map(|property| self.format_primitive(&property.children[0]))
+                .collect(),
+            _ => Vec::new(),
+        };
+        let key_width = self.key_width(&keys);
+        let column = std::mem::take(&mut self.column);
+        // the brackets and separators; the children are added as they come
+        let mut inline_width = self.indent_string().chars().count() + column + 2 * members.len();
+        let width = self.options.print_width;
+        let mut held = width.map(|_| Vec::new());
+        if held.is_none() {
+            output.write(&open.to_string())?;
+        }
+
+        self.up_indent();
+        for (index, member) in members.iter().enumerate() {
+            let (line, padding) = match keys.get(index) {
+                Some(key) => {
+                    let padding = key_width.saturating_sub(key.chars().count());
+                    self.column = self.property(key, padding, "").chars().count();
+                    let value = self.format_node(&member.children[1]);
+                    self.column = 0;
+                    (self.property(key, padding, &value), padding)
+                }
+                None => (self.format_node(member), 0),
+            };
+            let mut text = String::new();
+            self.push_newline(&mut text);
+            text.push_str(&self.indent_string());
+            text.push_str(&line);
+            if index + 1 < members.len() || self.use_trailing_commas() {
+                text.push(',');
+            }
+            let Some(texts) = &mut held else {
+                output.write(&text)?;
+                continue;
+            };
+            // on one line, `align_values` adds no padding
+            inline_width += line.chars().count() - padding;
+            texts.push(text);
+            if line.contains('\n') || width.is_some_and(|width| inline_width >= width) {
+                output.write(&open.to_string())?;
+                for text in held.take().into_iter().flatten() {
+                    output.write(&text)?;
+                }
+            }
+        }
+        self.down_indent();
+
+        if held.is_some() {
+            self.column = column;
+            let formatted = self.format_node(node);
+            return output.write(&formatted);
+        }
+        let mut text = String::new();
+        self.push_newline(&mut text);
+        text.push_str(&self.indent_string());
+        text.push(close);
+        output.write(&text)
+    }
+
+    /// Normalizes only the indentation of `input`, keeping every value on the
+    /// line it was written on so line-based patches still apply. `input` is
+    /// not checked to be valid JSON; parse it first to reject malformed input.
+    pub fn reindent(&mut self, input: &str) -> String {
+        let mut lines = Vec::new();
+        let mut in_string = false;
+        let mut escaped = false;
+        for line in input.split('\n') {
+            let trimmed = line.trim_start();
+            if trimmed.is_empty() {
+                lines.push(line.to_string());
+                continue;
+            }
+            let leading_closers = trimmed
+                .chars()
+                .take_while(|c| *c == '}' || *c == ']')
+                .count();
+            let depth = self.indent;
+            self.indent = depth.saturating_sub(leading_closers);
+            let mut s = self.indent_string();
+            s.push_str(trimmed);
+            lines.push(s);
+            self.indent = depth;
+            for c in trimmed.chars() {
+                if in_string {
+                    match c {
+                        _ if escaped => escaped = false,
+                        '\\' => escaped = true,
+                        '"' => in_string = false,
+                        _ => {}
+                    }
+                    continue;
+                }
+                match c {
+                    '"' => in_string = true,
+                    '{' | '[' => self.up_indent(),
+                    '}' | ']' => self.indent = self.indent.saturating_sub(1),
+                    _ => {}
+                }
+            }
+        }
+        self.indent = 0;
+        lines.join("\n")
+    }
+
+    pub fn format(&mut self, input: &str) -> String {
+        let mut parser = Parser::with_options(
+            input,
+            ParseOptions {
+                spans: !self.options.raw_pointers.is_empty(),
+                ..Default::default()
+            },
+        );
+        let node = parser.parse();
+        for span in parser.spans().iter().rev() {
+            if self.options.raw_pointers.contains(&span.pointer) {
+                self.raw
+                    .entry(span.pointer.clone())
+                    .or_default()
+                    .push(input[span.range.clone()].to_string());
+            }
+        }
+        self.pointer.clear();
+        let mut s = self.format_node(&node);
+        self.raw.clear();
+        self.push_final_newline(&mut s);
+        s
+    }
+
+    /// Like `format`, but also maps every value of `input` to where it ended
+    /// up in the output, e.g. to carry an editor's cursor across formatting.
+    pub fn format_with_source_map(
+        &mut self,
+        input: &str,
+    ) -> Result<(String, Vec<SourceMapping>), ParseError> {
+        let mut parser = Parser::with_options(
+            input,
+            ParseOptions {
+                spans: true,
+                ..Default::default()
+            },
+        );
+        let node = parser.try_parse()?;
+        let mut s = self.format_node(&node);
+        self.push_final_newline(&mut s);
+
+        // Layouts are assembled from strings, so the output positions are
+        // found by reading the output back.
+        let mut output_parser = Parser::with_options(
+            &s,
+            ParseOptions {
+                allow_trailing_commas: true,
+                allow_unquoted_keys: true,
+                allow_single_quotes: true,
+                allow_non_finite: true,
+                spans: true,
+                ..Default::default()
+            },
+        );
+        output_parser
+            .try_parse()
+            .expect("formatted output should parse");
+        // repeated keys share a pointer and are paired in document order
+        let mut outputs: HashMap<String, Vec<Range<usize>>> = HashMap::new();
+        for span in output_parser.spans().iter().rev() {
+            outputs
+                .entry(span.pointer.clone())
+                .or_default()
+                .push(span.range.clone());
+        }
+        drop(output_parser);
+        let mappings = parser
+            .spans()
+            .iter()
+            .filter_map(|span| {
+                let output = outputs.get_mut(&span.pointer)?.pop()?;
+                Some(SourceMapping {
+                    pointer: span.pointer.clone(),
+                    input: span.range.clone(),
+                    output,
+                })
+            })
+            .collect();
+        Ok((s, mappings))
+    }
+
+    /// Rewrites `input` as canonical JSON in the manner of RFC 8785, for
+    /// hashing and signing: members sorted by the UTF-16 code units of their
+    /// keys, no whitespace, numbers in their shortest ECMAScript form and
+    /// strings escaped only where JSON requires. Of repeated keys the last
+    /// one is kept.
+    pub fn canonicalize(input: &str) -> Result<String, ParseError> {
+        Ok(Formatter::canonicalize_node(
+            &Parser::new(input).try_parse()?,
+        ))
+    }
+
+    pub fn canonicalize_node(node: &Node) -> String {
+        let mut s = String::new();
+        write_canonical(node, &mut s);
+        s
+    }
+
+    /// The hex SHA-256 of the canonical form of `input`, equal for documents
+    /// that differ only in formatting, key order or number spelling.
+    #[cfg(feature = "hash")]
+    pub fn content_address(input: &str) -> Result<String, ParseError> {
+        use sha2::{Digest, Sha256};
+
+        let digest = Sha256::digest(Formatter::canonicalize(input)?.as_bytes());
+        Ok(digest.iter().map(|byte| format!("{:02x}", byte)).collect())
+    }
+
+    /// Ends a formatted document with a line break when `final_newline` is
+    /// set and it does not already end with one.
+    pub fn push_final_newline(&self, s: &mut String) {
+        if self.options.final_newline && !s.ends_with('\n') {
+            self.push_newline(s);
+        }
+    }
+}
+
+fn property_key(property: &Node) -> &str {
+    match property.children.first().map(|key| &key.kind) {
+        Some(SyntaxKind::Identifier(key)) => key,
+        _ => "",
+    }
+}
+
+/// Where `SortMode::TypeThenKeys` puts a property: primitive values, then
+/// arrays, then objects.
+fn value_rank(property: &Node) -> u8 {
+    match property.children.get(1).map(|value| &value.kind) {
+        Some(SyntaxKind::ArrayLiteralExpression) => 1,
+        Some(SyntaxKind::ObjectLiteralExpression) => 2,
+        _ => 0,
+    }
+}
+
+fn is_scalar(node: &Node) -> bool {
+    !matches!(
+        node.kind,
+        SyntaxKind::ObjectLiteralExpression | SyntaxKind::ArrayLiteralExpression
+    )
+}
+
+/// Verifies that every node sits where the formatter can render it: values in
+/// value positions and only key/value property assignments inside objects.
+fn check_tree(root: &Node) -> Result<(), FormatError> {
+    let mut stack = vec![(root, String::new())];
+    while let Some((node, pointer)) = stack.pop() {
+        match &node.kind {
+            SyntaxKind::ObjectLiteralExpression => {
+                for (index, property) in node.children.iter().enumerate() {
+                    let key = match (&property.kind, property.children.as_slice()) {
+                        (SyntaxKind::PropertyAssignment, [key, _]) => match &key.kind {
+                            SyntaxKind::Identifier(key) => key,
+                            _ => {
+                                return Err(FormatError::MalformedProperty {
+                                    pointer: format!("{}/{}", pointer, index),
+                                })
+                            }
+                        },
+                        (SyntaxKind::PropertyAssignment, _) => {
+                            return Err(FormatError::MalformedProperty {
+                                pointer: format!("{}/{}", pointer, index),
+                            })
+                        }
+                        (kind, _) => {
+                            return Err(FormatError::UnexpectedNode {
+                                pointer: format!("{}/{}", pointer, index),
+                                kind: kind.clone(),
+                            })
+                        }
+                    };
+                    let child_pointer = format!("{}/{}", pointer, escape_pointer_token(key));
+                    stack.push((&property.children[1], child_pointer));
+                }
+            }
+            SyntaxKind::ArrayLiteralExpression => {
+                for (index, element) in node.children.iter().enumerate() {
+                    stack.push((element, format!("{}/{}", pointer, index)));
+                }
+            }
+            SyntaxKind::PropertyAssignment | SyntaxKind::Identifier(_) => {
+                return Err(FormatError::UnexpectedNode {
+                    pointer,
+                    kind: node.kind.clone(),
+                })
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Formats a parsed tree with the default options, handy for debugging.
+///
+/// ```
+/// use formatter::format::Pretty;
+/// use json_parser::parse::Parser;
+///
+/// let node = Parser::new(r#"{"a": [1, 2]}"#).parse();
+/// assert_eq!(node.pretty(), "{\n    \"a\": [\n        1,\n        2\n    ]\n}");
+/// ```
+pub trait Pretty {
+    fn pretty(&self) -> String;
+}
+
+impl Pretty for Node {
+    fn pretty(&self) -> String {
+        Formatter::new(None).format_node(self)
+    }
+}
+
+/// Formats a `Value` built in code, the pretty counterpart of
+/// `Value::to_string_compact`.
+pub trait ToStringPretty {
+    fn to_string_pretty(&self, options: &FormatOptions) -> String;
+}
+
+impl ToStringPretty for Value {
+    fn to_string_pretty(&self, options: &FormatOptions) -> String {
+        Formatter::new(Some(options.clone())).format_node(&self.to_node())
+    }
+}
+
+fn round(value: f64, places: u32) -> f64 {
+    let factor = 10f64.powi(places.min(i32::MAX as u32) as i32);
+    let scaled = value * factor;
+    // a value this large, or this many places, leaves no digits to round off
+    if !scaled.is_finite() {
+        return value;
+    }
+    let rounded = scaled.round() / factor;
+    // avoid printing "-0" for small negative values
+    if rounded == 0.0 {
+        0.0
+    } else {
+        rounded
+    }
+}
+
+fn write_canonical(node: &Node, s: &mut String) {
+    match &node.kind {
+        SyntaxKind::ObjectLiteralExpression => {
+            let members: BTreeMap<Vec<u16>, (&str, &Node)> = node
+                .children
+                .iter()
+                .map(|property| {
+                    let key = property_key(property);
+                    (key.encode_utf16().collect(), (key, &property.children[1]))
+                })
+                .collect();
+            s.push('{');
+            for (index, (key, value)) in members.into_values().enumerate() {
+                if index > 0 {
+                    s.push(',');
+                }
+                s.push_str(&escape_string_with_quote(key, '"'));
+                s.push(':');
+                write_canonical(value, s);
+            }
+            s.push('}');
+        }
+        SyntaxKind::ArrayLiteralExpression => {
+            s.push('[');
+            for (index, element) in node.children.iter().enumerate() {
+                if index > 0 {
+                    s.push(',');
+                }
+                write_canonical(element, s);
+            }
+            s.push(']');
+        }
+        SyntaxKind::StringLiteral(text) | SyntaxKind::Identifier(text) => {
+            s.push_str(&escape_string_with_quote(text, '"'))
+        }
+        SyntaxKind::NumberLiteral(number) => s.push_str(&canonical_number(number.value)),
+        #[cfg(feature = "decimal")]
+        SyntaxKind::DecimalLiteral(value) => {
+            use json_parser::rust_decimal::prelude::ToPrimitive;
+            s.push_str(&canonical_number(value.to_f64().unwrap_or(f64::NAN)))
+        }
+        SyntaxKind::TrueKeyword => s.push_str("true"),
+        SyntaxKind::FalseKeyword => s.push_str("false"),
+        SyntaxKind::NullKeyword => s.push_str("null"),
+        SyntaxKind::PropertyAssignment => {
+            unreachable!("write_canonical called on a property, {:?}", node)
+        }
+    }
+}
+
+/// Spells `value` the way ECMAScript's `Number.prototype.toString` does,
+/// which RFC 8785 adopts: the shortest digits that round-trip, in plain
+/// notation for exponents from -7 to 20 and scientific notation otherwise.
+/// Non-finite values become null, as in JSON.stringify.
+fn canonical_number(value: f64) -> String {
+    if !value.is_finite() {
+        return "null".to_string();
+    }
+    if value == 0.0 {
+        // covers -0 too
+        return "0".to_string();
+    }
+    let sign = if value < 0.0 { "-" } else { "" };
+    // `{:e}` yields the shortest round-trip digits as `d.ddde-7`
+    let scientific = format!("{:e}", value.abs());
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let digits = mantissa.replace('.', "");
+    // the decimal point goes after `point` digits
+    let point = exponent.parse::<i32>().unwrap() + 1;
+    let k = digits.len() as i32;
+    let body = if k <= point && point <= 21 {
+        format!("{}{}", digits, "0".repeat((point - k) as usize))
+    } else if 0 < point && point <= 21 {
+        format!(
+            "{}.{}",
+            &digits[..point as usize],
+            &digits[point as usize..]
+        )
+    } else if -6 < point && point <= 0 {
+        format!("0.{}{}", "0".repeat(-point as usize), digits)
+    } else {
+        let exponent_sign = if point > 0 { "+" } else { "-" };
+        let mantissa = match digits.split_at(1) {
+            (first, "") => first.to_string(),
+            (first, rest) => format!("{}.{}", first, rest),
+        };
+        format!("{}e{}{}", mantissa, exponent_sign, (point - 1).abs())
+    };
+    format!("{}{}", sign, body)
+}
+
+/// Rewrites a JSON number such as `-1.25e3` without its exponent by moving
+/// the decimal point, keeping every digit as written.
+fn expand_exponent(number: &str) -> String {
+    let Some((mantissa, exponent)) = number.split_once(['e', 'E']) else {
+        return number.to_string();
+    };
+    let Ok(exponent) = exponent.parse::<i64>() else {
+        return number.to_string();
+    };
+    let (sign, mantissa) = match mantissa.strip_prefix('-') {
+        Some(unsigned) => ("-", unsigned),
+        None => ("", mantissa),
+    };
+    let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits = format!("{}{}", integer, fraction);
+    // the decimal point goes after `point` digits
+    let point = integer.len() as i64 + exponent;
+    let expanded = if point <= 0 {
+        format!("0.{}{}", "0".repeat(-point as usize), digits)
+    } else if point as usize >= digits.len() {
+        format!("{}{}", digits, "0".repeat(point as usize - digits.len()))
+    } else {
+        let (integer, fraction) = digits.split_at(point as usize);
+        format!("{}.{}", integer, fraction)
+    };
+    // shifting can leave zeros in front of the integer part, as in `0.05e2`
+    let integer_end = expanded.find('.').unwrap_or(expanded.len());
+    let leading_zeros = expanded[..integer_end.saturating_sub(1)]
+        .chars()
+        .take_while(|&c| c == '0')
+        .count();
+    format!("{}{}", sign, &expanded[leading_zeros..])
+}
+
+/// Drops the zeros ending the fraction of a number, and the decimal point if
+/// nothing is left after it. An exponent is kept as it is.
+fn trim_fraction_zeros(number: &str) -> String {
+    let (mantissa, exponent) = match number.find(['e', 'E']) {
+        Some(index) => number.split_at(index),
+        None => (number, ""),
+    };
+    if !mantissa.contains('.') {
+        return number.to_string();
+    }
+    let mantissa = mantissa.trim_end_matches('0').trim_end_matches('.');
+    format!("{}{}", mantissa, exponent)
+}
+
+fn pad_fraction(number: &str, digits: usize) -> String {
+    let (mantissa, exponent) = match number.find(['e', 'E']) {
+        Some(index) => number.split_at(index),
+        None => (number, ""),
+    };
+    let (point, fraction) = match mantissa.split_once('.') {
+        Some((_, fraction)) => ("", fraction.len()),
+        None => (".", 0),
+    };
+    if fraction >= digits {
+        return number.to_string();
+    }
+    format!(
+        "{}{}{}{}",
+        mantissa,
+        point,
+        "0".repeat(digits - fraction),
+        exponent
+    )
+}
+
+fn is_identifier_name(text: &str) -> bool {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' || c == '$' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+        }
+        _ => false,
+    }
+}
+
+pub fn find_long_lines(formatted: &str, max_line_length: usize) -> Vec<(usize, &str)> {
+    formatted
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| line.chars().count() > max_line_length)
+        .map(|(index, line)| (index + 1, line))
+        .collect()
+}
+
+/// Returns the 1-based numbers of lines whose indentation uses a different
+/// character (tab or space) than the first indented line of `input`.
+pub fn find_mixed_indentation(input: &str) -> Vec<usize> {
+    let mut expected = None;
+    let mut lines = Vec::new();
+    for (index, line) in input.lines().enumerate() {
+        let indent: Vec<char> = line
+            .chars()
+            .take_while(|c| *c == ' ' || *c == '\t')
+            .collect();
+        let first = match indent.first() {
+            Some(first) => *first,
+            None => continue,
+        };
+        let expected = *expected.get_or_insert(first);
+        if indent.iter().any(|c| *c != expected) {
+            lines.push(index + 1);
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use json_parser::{node::SyntaxKind, parse::ParseOptions};
+
+    use super::*;
+
+    #[test]
+    fn test_format_options_builder() {
+        let options = FormatOptions::builder()
+            .spaces(2)
+            .trailing_commas(true)
+            .line_ending(LineEnding::Crlf)
+            .round_to(3)
+            .build();
+        assert_eq!(
+            options,
+            FormatOptions {
+                spaces: 2,
+                trailing_commas: true,
+                line_ending: LineEnding::Crlf,
+                round_to: Some(3),
+                ..Default::default()
+            }
+        );
+        assert_eq!(FormatOptions::builder().build(), FormatOptions::default());
+    }
+
+    #[test]
+    fn test_indent_string() {
+        let cases = vec![
+            (None, "    "),
+            (
+                Some(FormatOptions {
+                    spaces: 2,
+                    ..Default::default()
+                }),
+                "  ",
+            ),
+            (
+                Some(FormatOptions {
+                    use_tabs: true,
+                    ..Default::default()
+                }),
+                "\t",
+            ),
+            (
+                Some(FormatOptions {
+                    spaces: 2,
+                    use_tabs: true,
+                    ..Default::default()
+                }),
+                "\t",
+            ),
+        ];
 
         for (options, expected) in cases {
             let mut formatter = Formatter::new(options);
@@ -188,232 +1921,1687 @@ mod tests {
     }
 
     #[test]
-    fn test_format_primitive() {
+    fn test_format_primitive() {
+        let cases = vec![
+            (
+                Node::new(SyntaxKind::StringLiteral("hello".to_string()), vec![]),
+                "\"hello\"".to_string(),
+            ),
+            (
+                Node::new(SyntaxKind::NumberLiteral(42.0.into()), vec![]),
+                "42".to_string(),
+            ),
+            (
+                Node::new(SyntaxKind::TrueKeyword, vec![]),
+                "true".to_string(),
+            ),
+            (
+                Node::new(SyntaxKind::FalseKeyword, vec![]),
+                "false".to_string(),
+            ),
+            (
+                Node::new(SyntaxKind::NullKeyword, vec![]),
+                "null".to_string(),
+            ),
+        ];
+
+        for (node, expected) in cases {
+            let formatter = Formatter::new(None);
+            assert_eq!(formatter.format_primitive(&node), expected);
+        }
+    }
+
+    #[test]
+    fn test_format_primitive_without_exponent() {
+        let cases = vec![
+            (1e21, "1000000000000000000000"),
+            (1.5e25, "15000000000000000000000000"),
+            (1e-7, "0.0000001"),
+            (2.5e-10, "0.00000000025"),
+        ];
+
+        for (value, expected) in cases {
+            let formatter = Formatter::new(None);
+            let node = Node::new(SyntaxKind::NumberLiteral(value.into()), vec![]);
+            assert_eq!(formatter.format_primitive(&node), expected);
+        }
+    }
+
+    #[test]
+    fn test_format_primitive_non_finite() {
+        let cases = vec![
+            (Dialect::Json, f64::INFINITY, "null"),
+            (Dialect::Json, f64::NAN, "null"),
+            (Dialect::Json5, f64::INFINITY, "Infinity"),
+            (Dialect::Json5, f64::NEG_INFINITY, "-Infinity"),
+            (Dialect::Json5, f64::NAN, "NaN"),
+        ];
+
+        for (dialect, value, expected) in cases {
+            let formatter = Formatter::new(Some(FormatOptions {
+                dialect,
+                round_to: Some(2),
+                ..Default::default()
+            }));
+            let node = Node::new(SyntaxKind::NumberLiteral(value.into()), vec![]);
+            assert_eq!(formatter.format_primitive(&node), expected);
+        }
+    }
+
+    #[test]
+    fn test_format_primitive_round_to() {
+        let cases = vec![
+            (Some(2), 1.23456, "1.23"),
+            (Some(2), 9.876, "9.88"),
+            (Some(2), 42.0, "42"),
+            (Some(2), -0.001, "0"),
+            (Some(0), 2.5, "3"),
+            (None, 1.23456, "1.23456"),
+        ];
+
+        for (round_to, value, expected) in cases {
+            let formatter = Formatter::new(Some(FormatOptions {
+                round_to,
+                ..Default::default()
+            }));
+            let node = Node::new(SyntaxKind::NumberLiteral(value.into()), vec![]);
+            assert_eq!(formatter.format_primitive(&node), expected);
+        }
+
+        // scaling these overflows; they come out unchanged rather than as
+        // inf or NaN
+        let cases = vec![
+            (2, 1.7e308),
+            (2, f64::MAX),
+            (2, -f64::MAX),
+            (400, 1.5),
+            (400, 0.0),
+            (u32::MAX, 0.1),
+        ];
+        for (places, value) in cases {
+            let formatter = Formatter::new(Some(FormatOptions::builder().round_to(places).build()));
+            let node = Node::new(SyntaxKind::NumberLiteral(value.into()), vec![]);
+            assert_eq!(
+                formatter.format_primitive(&node),
+                value.to_string(),
+                "{}",
+                value
+            );
+        }
+        let mut formatter = Formatter::new(Some(FormatOptions::builder().round_to(2).build()));
+        // integers past 2^53 keep every digit
+        assert_eq!(
+            formatter.format("[9007199254740993, -12345678901234567890, 0.125]"),
+            "[\n    9007199254740993,\n    -12345678901234567890,\n    0.13\n]\n"
+        );
+        assert_eq!(
+            formatter.format("[1.7e308]"),
+            format!("[\n    {}\n]\n", 1.7e308)
+        );
+    }
+
+    #[test]
+    fn test_format_array() {
+        let cases = vec![
+            (
+                Node::new(
+                    SyntaxKind::ArrayLiteralExpression,
+                    vec![
+                        Node::new(SyntaxKind::NumberLiteral(1.0.into()), vec![]),
+                        Node::new(SyntaxKind::NumberLiteral(2.0.into()), vec![]),
+                    ],
+                ),
+                "[\n    1,\n    2\n]".to_string(),
+                "[\n    1,\n    2,\n]".to_string(),
+            ),
+            (
+                Node::new(
+                    SyntaxKind::ArrayLiteralExpression,
+                    vec![
+                        Node::new(
+                            SyntaxKind::ArrayLiteralExpression,
+                            vec![Node::new(SyntaxKind::NumberLiteral(1.0.into()), vec![])],
+                        ),
+                        Node::new(
+                            SyntaxKind::ArrayLiteralExpression,
+                            vec![Node::new(SyntaxKind::NumberLiteral(2.0.into()), vec![])],
+                        ),
+                    ],
+                ),
+                "[\n    [\n        1\n    ],\n    [\n        2\n    ]\n]".to_string(),
+                "[\n    [\n        1,\n    ],\n    [\n        2,\n    ],\n]".to_string(),
+            ),
+        ];
+
+        for (node, expected, expected_with_trailing_commas) in cases {
+            let mut formatter = Formatter::new(None);
+            assert_eq!(formatter.format_array(&node), expected);
+            formatter.options.trailing_commas = true;
+            assert_eq!(formatter.format_array(&node), expected_with_trailing_commas);
+        }
+    }
+
+    #[test]
+    fn test_format_object() {
+        let cases = vec![
+            (
+                Node::new(
+                    SyntaxKind::ObjectLiteralExpression,
+                    vec![
+                        Node::new(
+                            SyntaxKind::PropertyAssignment,
+                            vec![
+                                Node::new(SyntaxKind::StringLiteral("hello".to_string()), vec![]),
+                                Node::new(SyntaxKind::StringLiteral("world".to_string()), vec![]),
+                            ],
+                        ),
+                    ],
+                ),
+                "{\n    \"hello\": \"world\"\n}".to_string(),
+                "{\n    \"hello\": \"world\",\n}".to_string(),
+            ),
+            (
+                Node::new(
+                    SyntaxKind::ObjectLiteralExpression,
+                    vec![
+                        Node::new(
+                            SyntaxKind::PropertyAssignment,
+                            vec![
+                                Node::new(SyntaxKind::StringLiteral("hello".to_string()), vec![]),
+                                Node::new(
+                                    SyntaxKind::ObjectLiteralExpression,
+                                    vec![
+                                        Node::new(
+                                            SyntaxKind::PropertyAssignment,
+                                            vec![
+                                                Node::new(SyntaxKind::StringLiteral("foo".to_string()), vec![]),
+                                                Node::new(SyntaxKind::NumberLiteral(42.0.into()), vec![]),
+                                            ],
+                                        ),
+                                    ],
+                                ),
+                            ],
+                        ),
+                        Node::new(
+                            SyntaxKind::PropertyAssignment,
+                            vec![
+                                Node::new(SyntaxKind::StringLiteral("world".to_string()), vec![]),
+                                Node::new(
+                                    SyntaxKind::ObjectLiteralExpression,
+                                    vec![
+                                        Node::new(
+                                            SyntaxKind::PropertyAssignment,
+                                            vec![
+                                                Node::new(SyntaxKind::StringLiteral("bar".to_string()), vec![]),
+                                                Node::new(SyntaxKind::NumberLiteral(42.0.into()), vec![]),
+                                            ],
+                                        ),
+                                    ],
+                                ),
+                            ],
+                        ),
+                    ],
+                ),
+                "{\n    \"hello\": {\n        \"foo\": 42\n    },\n    \"world\": {\n        \"bar\": 42\n    }\n}".to_string(),
+                "{\n    \"hello\": {\n        \"foo\": 42,\n    },\n    \"world\": {\n        \"bar\": 42,\n    },\n}".to_string(),
+            ),
+        ];
+
+        for (node, expected, expected_with_trailing_commas) in cases {
+            let mut formatter = Formatter::new(None);
+            assert_eq!(formatter.format_object(&node), expected);
+            formatter.options.trailing_commas = true;
+            assert_eq!(
+                formatter.format_object(&node),
+                expected_with_trailing_commas
+            );
+        }
+    }
+
+    #[test]
+    fn test_format_node() {
+        let cases = vec![
+            (
+                Node::new(
+                    SyntaxKind::ArrayLiteralExpression,
+                    vec![
+                        Node::new(SyntaxKind::StringLiteral("hello".to_string()), vec![]),
+                        Node::new(SyntaxKind::NumberLiteral(42.0.into()), vec![]),
+                    ],
+                ),
+                "[\n    \"hello\",\n    42\n]".to_string(),
+            ),
+            (
+                Node::new(
+                    SyntaxKind::ObjectLiteralExpression,
+                    vec![
+                        Node::new(
+                            SyntaxKind::PropertyAssignment,
+                            vec![
+                                Node::new(SyntaxKind::StringLiteral("hello".to_string()), vec![]),
+                                Node::new(SyntaxKind::ObjectLiteralExpression, vec![
+                                    Node::new(
+                                        SyntaxKind::PropertyAssignment,
+                                        vec![
+                                            Node::new(SyntaxKind::StringLiteral("foo".to_string()), vec![]),
+                                            Node::new(SyntaxKind::NumberLiteral(42.0.into()), vec![]),
+                                        ],
+                                    ),
+                                ]),
+                            ],
+                        ),
+                        Node::new(
+                            SyntaxKind::PropertyAssignment,
+                            vec![
+                                Node::new(SyntaxKind::StringLiteral("world".to_string()), vec![]),
+                                Node::new(SyntaxKind::ArrayLiteralExpression, vec![
+                                    Node::new(SyntaxKind::StringLiteral("bar".to_string()), vec![]),
+                                    Node::new(SyntaxKind::NumberLiteral(42.0.into()), vec![]),
+                                ]),
+                            ],
+                        ),
+                    ],
+                ),
+                "{\n    \"hello\": {\n        \"foo\": 42\n    },\n    \"world\": [\n        \"bar\",\n        42\n    ]\n}".to_string(),
+            ),
+        ];
+
+        for (node, expected) in cases {
+            let mut formatter = Formatter::new(None);
+            assert_eq!(formatter.format_node(&node), expected);
+        }
+    }
+
+    #[test]
+    fn test_format() {
+        let cases = vec![
+            (
+                r#"{"hello": "world"}"#,
+                "{\n    \"hello\": \"world\"\n}".to_string(),
+            ),
+            (
+                r#"[1, 2, 3]"#,
+                "[\n    1,\n    2,\n    3\n]".to_string(),
+            ),
+            (
+                r#"{"hello": {"foo": 42}, "world": ["bar", 42]}"#,
+                "{\n    \"hello\": {\n        \"foo\": 42\n    },\n    \"world\": [\n        \"bar\",\n        42\n    ]\n}".to_string(),
+            ),
+            (
+                r#"[{"hello": "world"}, {"foo": "bar"}]"#,
+                "[\n    {\n        \"hello\": \"world\"\n    },\n    {\n        \"foo\": \"bar\"\n    }\n]".to_string(),
+            ),
+            (r#"  42 "#, "42".to_string()),
+            (r#""hi""#, "\"hi\"".to_string()),
+        ];
+
+        for (input, expected) in cases {
+            let mut formatter = Formatter::new(None);
+            assert_eq!(formatter.format(input), format!("{}\n", expected));
+        }
+    }
+
+    #[test]
+    fn test_format_preserves_number_lexemes() {
+        let cases = vec![
+            (
+                None,
+                "[1.0, 2.50, 10000000000000001, 1e3, -0]",
+                "[\n    1.0,\n    2.50,\n    10000000000000001,\n    1e3,\n    -0\n]",
+            ),
+            (
+                Some(2),
+                "[1.50, 2.00, 0.10, 100.0]",
+                "[\n    1.5,\n    2,\n    0.1,\n    100\n]",
+            ),
+        ];
+
+        for (round_to, input, expected) in cases {
+            let mut formatter = Formatter::new(Some(FormatOptions {
+                round_to,
+                ..Default::default()
+            }));
+            assert_eq!(formatter.format(input), format!("{}\n", expected));
+        }
+    }
+
+    #[test]
+    fn test_format_trims_trailing_zeros() {
+        let mut formatter = Formatter::new(Some(
+            FormatOptions::builder().trim_trailing_zeros(true).build(),
+        ));
+        assert_eq!(
+            formatter.format("[1.50, 2.00, 0.10, 10, 100.0]"),
+            "[\n    1.5,\n    2,\n    0.1,\n    10,\n    100\n]\n"
+        );
+
+        let cases = vec![
+            ("-0.500", "-0.5"),
+            ("1.50e3", "1.5e3"),
+            ("2.0E-2", "2E-2"),
+            ("100", "100"),
+            ("1e10", "1e10"),
+            ("0.0", "0"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(formatter.format(input), format!("{}\n", expected));
+        }
+    }
+
+    #[test]
+    fn test_format_no_exponential() {
+        let cases = vec![
+            ("1e21", "1000000000000000000000"),
+            ("1.5E+25", "15000000000000000000000000"),
+            ("-2.50e3", "-2500"),
+            ("1.25e1", "12.5"),
+            ("1e-7", "0.0000001"),
+            ("2.5e-10", "0.00000000025"),
+            ("0.05e2", "5"),
+            ("12.5e-1", "1.25"),
+            ("1.5", "1.5"),
+        ];
+
+        for (input, expected) in cases {
+            let mut formatter =
+                Formatter::new(Some(FormatOptions::builder().no_exponential(true).build()));
+            assert_eq!(
+                formatter.format(input),
+                format!("{}\n", expected),
+                "{}",
+                input
+            );
+        }
+
+        // without the option the exponent is kept as written
+        assert_eq!(Formatter::new(None).format("1e21"), "1e21\n");
+        let mut formatter = Formatter::new(Some(
+            FormatOptions::builder()
+                .no_exponential(true)
+                .trim_trailing_zeros(true)
+                .build(),
+        ));
+        assert_eq!(formatter.format("1.500e2"), "150\n");
+    }
+
+    #[test]
+    fn test_format_number_styles() {
+        let input = "[1, -20, 2.5, 3.0, 1e3, 2.5E-1, 1.125]";
+        let float = |no_exponential, min_fraction_digits| NumberStyle {
+            no_exponential,
+            min_fraction_digits,
+        };
+        let cases = vec![
+            (
+                NumberStyle::default(),
+                float(false, 1),
+                "[1, -20, 2.5, 3.0, 1.0e3, 2.5E-1, 1.125]",
+            ),
+            (
+                NumberStyle::default(),
+                float(true, 1),
+                "[1, -20, 2.5, 3.0, 1000.0, 0.25, 1.125]",
+            ),
+            (
+                float(false, 2),
+                float(false, 0),
+                "[1.00, -20.00, 2.5, 3.0, 1e3, 2.5E-1, 1.125]",
+            ),
+        ];
+        for (integer_style, float_style, expected) in cases {
+            let mut formatter = Formatter::new(Some(
+                FormatOptions::builder()
+                    .compact_primitive_arrays(true)
+                    .integer_style(integer_style)
+                    .float_style(float_style)
+                    .build(),
+            ));
+            assert_eq!(formatter.format(input), format!("{}\n", expected));
+        }
+
+        // floats stay floats when trimmed or rounded to whole numbers
+        let mut formatter = Formatter::new(Some(
+            FormatOptions::builder()
+                .trim_trailing_zeros(true)
+                .round_to(0)
+                .float_style(float(false, 1))
+                .compact_primitive_arrays(true)
+                .build(),
+        ));
+        assert_eq!(formatter.format("[2.00, 7, 1.6]"), "[2.0, 7, 2.0]\n");
+    }
+
+    #[test]
+    fn test_format_lenient_keys() {
+        let node = Parser::with_options(
+            r#"{1: "a", true: "b"}"#,
+            ParseOptions {
+                lenient: true,
+                ..Default::default()
+            },
+        )
+        .parse();
+        let mut formatter = Formatter::new(None);
+        assert_eq!(
+            formatter.format_node(&node),
+            "{\n    \"1\": \"a\",\n    \"true\": \"b\"\n}"
+        );
+    }
+
+    #[cfg(feature = "decimal")]
+    #[test]
+    fn test_format_decimal() {
+        let cases = vec![
+            (
+                None,
+                r#"[0.1, 1.10, 12345678901234567.89]"#,
+                "[\n    0.1,\n    1.10,\n    12345678901234567.89\n]",
+            ),
+            (Some(1), r#"[0.25, -0.35]"#, "[\n    0.3,\n    -0.4\n]"),
+        ];
+
+        for (round_to, input, expected) in cases {
+            let node = Parser::with_options(
+                input,
+                ParseOptions {
+                    decimal: true,
+                    ..Default::default()
+                },
+            )
+            .parse();
+            let mut formatter = Formatter::new(Some(FormatOptions {
+                round_to,
+                ..Default::default()
+            }));
+            assert_eq!(formatter.format_node(&node), expected);
+        }
+    }
+
+    #[test]
+    fn test_format_print_width() {
+        let cases = vec![
+            (40, r#"[1, 2, 3]"#, "[1, 2, 3]"),
+            (9, r#"[1, 2, 3]"#, "[\n    1,\n    2,\n    3\n]"),
+            (
+                30,
+                r#"{"short": [1, 2], "long": ["aaaaaaaa", "bbbbbbbb", "cccccccc"]}"#,
+                r#"{
+    "short": [1, 2],
+    "long": [
+        "aaaaaaaa",
+        "bbbbbbbb",
+        "cccccccc"
+    ]
+}"#,
+            ),
+            (
+                24,
+                r#"[{"a": [1, 2]}, {"b": {"c": [3, 4], "d": null}}]"#,
+                r#"[
+    {"a": [1, 2]},
+    {
+        "b": {
+            "c": [3, 4],
+            "d": null
+        }
+    }
+]"#,
+            ),
+        ];
+
+        for (print_width, input, expected) in cases {
+            let mut formatter = Formatter::new(Some(
+                FormatOptions::builder().print_width(print_width).build(),
+            ));
+            assert_eq!(
+                formatter.format(input),
+                format!("{}\n", expected),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_format_ascii_only() {
+        let input = "{\"caf\u{e9}\": \"\u{1F600} cr\u{e8}me\"}";
+        let cases = vec![
+            (
+                false,
+                Dialect::Json,
+                "{\n    \"caf\u{e9}\": \"\u{1F600} cr\u{e8}me\"\n}",
+            ),
+            (
+                true,
+                Dialect::Json,
+                r#"{
+    "caf\u00e9": "\ud83d\ude00 cr\u00e8me"
+}"#,
+            ),
+            (
+                true,
+                Dialect::Json5,
+                r#"{
+    'caf\u00e9': '\ud83d\ude00 cr\u00e8me',
+}"#,
+            ),
+        ];
+
+        for (ascii_only, dialect, expected) in cases {
+            let mut formatter = Formatter::new(Some(
+                FormatOptions::builder()
+                    .ascii_only(ascii_only)
+                    .dialect(dialect)
+                    .build(),
+            ));
+            assert_eq!(formatter.format(input), format!("{}\n", expected));
+        }
+    }
+
+    #[test]
+    fn test_format_compact_primitive_arrays() {
+        let cases = vec![
+            (None, r#"[1, 2.5, -3]"#, r#"[1, 2.5, -3]"#),
+            (
+                None,
+                r#"{"tags": ["a", true, null], "n": [1]}"#,
+                r#"{
+    "tags": ["a", true, null],
+    "n": [1]
+}"#,
+            ),
+            (
+                None,
+                r#"[1, {"a": 2}]"#,
+                r#"[
+    1,
+    {
+        "a": 2
+    }
+]"#,
+            ),
+            (
+                None,
+                r#"[[1, 2], [3, 4]]"#,
+                r#"[
+    [1, 2],
+    [3, 4]
+]"#,
+            ),
+            (
+                Some(20),
+                r#"{"short": [1, 2], "long": [100, 200, 300]}"#,
+                r#"{
+    "short": [1, 2],
+    "long": [
+        100,
+        200,
+        300
+    ]
+}"#,
+            ),
+        ];
+
+        for (print_width, input, expected) in cases {
+            let mut builder = FormatOptions::builder().compact_primitive_arrays(true);
+            if let Some(print_width) = print_width {
+                builder = builder.print_width(print_width);
+            }
+            let mut formatter = Formatter::new(Some(builder.build()));
+            assert_eq!(
+                formatter.format(input),
+                format!("{}\n", expected),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_format_wrap_scalar_arrays() {
+        let input = r#"{"words": ["alpha", "beta", "gamma", "delta", "epsilon", "zeta", "eta", "theta"], "n": [1, 2, 3]}"#;
+        let cases = vec![
+            (
+                FormatOptions::builder()
+                    .wrap_scalar_arrays(true)
+                    .print_width(30)
+                    .build(),
+                r#"{
+    "words": [
+        "alpha", "beta",
+        "gamma", "delta",
+        "epsilon", "zeta",
+        "eta", "theta"
+    ],
+    "n": [1, 2, 3]
+}"#,
+            ),
+            (
+                FormatOptions::builder()
+                    .wrap_scalar_arrays(true)
+                    .print_width(4)
+                    .build(),
+                r#"{
+    "words": [
+        "alpha",
+        "beta",
+        "gamma",
+        "delta",
+        "epsilon",
+        "zeta",
+        "eta",
+        "theta"
+    ],
+    "n": [
+        1,
+        2,
+        3
+    ]
+}"#,
+            ),
+            (
+                FormatOptions::builder().wrap_scalar_arrays(true).build(),
+                r#"{
+    "words": [
+        "alpha",
+        "beta",
+        "gamma",
+        "delta",
+        "epsilon",
+        "zeta",
+        "eta",
+        "theta"
+    ],
+    "n": [
+        1,
+        2,
+        3
+    ]
+}"#,
+            ),
+        ];
+
+        for (options, expected) in cases {
+            let mut formatter = Formatter::new(Some(options));
+            assert_eq!(formatter.format(input), format!("{}\n", expected));
+        }
+    }
+
+    #[test]
+    fn test_try_format_node() {
+        let number = || Node::new(SyntaxKind::NumberLiteral(1.0.into()), vec![]);
+        let key = |name: &str| Node::new(SyntaxKind::Identifier(name.to_string()), vec![]);
+        let cases = vec![
+            (
+                Node::new(SyntaxKind::ArrayLiteralExpression, vec![number()]),
+                Ok("[\n    1\n]".to_string()),
+            ),
+            (
+                Node::new(
+                    SyntaxKind::ArrayLiteralExpression,
+                    vec![
+                        number(),
+                        Node::new(SyntaxKind::PropertyAssignment, vec![key("a"), number()]),
+                    ],
+                ),
+                Err(FormatError::UnexpectedNode {
+                    pointer: "/1".to_string(),
+                    kind: SyntaxKind::PropertyAssignment,
+                }),
+            ),
+            (
+                Node::new(
+                    SyntaxKind::ObjectLiteralExpression,
+                    vec![Node::new(
+                        SyntaxKind::PropertyAssignment,
+                        vec![
+                            key("a/b"),
+                            Node::new(SyntaxKind::ArrayLiteralExpression, vec![key("x")]),
+                        ],
+                    )],
+                ),
+                Err(FormatError::UnexpectedNode {
+                    pointer: "/a~1b/0".to_string(),
+                    kind: SyntaxKind::Identifier("x".to_string()),
+                }),
+            ),
+            (
+                Node::new(
+                    SyntaxKind::ObjectLiteralExpression,
+                    vec![Node::new(SyntaxKind::PropertyAssignment, vec![key("a")])],
+                ),
+                Err(FormatError::MalformedProperty {
+                    pointer: "/0".to_string(),
+                }),
+            ),
+            (
+                Node::new(SyntaxKind::ObjectLiteralExpression, vec![number()]),
+                Err(FormatError::UnexpectedNode {
+                    pointer: "/0".to_string(),
+                    kind: SyntaxKind::NumberLiteral(1.0.into()),
+                }),
+            ),
+        ];
+
+        for (node, expected) in cases {
+            let mut formatter = Formatter::new(None);
+            assert_eq!(formatter.try_format_node(&node), expected);
+        }
+    }
+
+    #[test]
+    fn test_format_escapes_strings() {
+        let cases = vec![
+            (
+                Dialect::Json,
+                r#"{"say \"hi\"": "C:\\dir\nnext\u0001"}"#,
+                r#"{
+    "say \"hi\"": "C:\\dir\nnext\u0001"
+}"#,
+            ),
+            (
+                Dialect::Json5,
+                r#"{"it's": "say \"hi\"\t"}"#,
+                r#"{
+    'it\'s': 'say "hi"\t',
+}"#,
+            ),
+        ];
+
+        for (dialect, input, expected) in cases {
+            let mut formatter = Formatter::new(Some(FormatOptions {
+                dialect,
+                ..Default::default()
+            }));
+            let formatted = formatter.format(input);
+            assert_eq!(formatted, format!("{}\n", expected));
+            if dialect == Dialect::Json {
+                assert_eq!(Parser::new(&formatted).parse(), Parser::new(input).parse());
+            }
+        }
+    }
+
+    #[test]
+    fn test_format_empty_containers_with_trailing_commas() {
+        let cases = vec![
+            (false, "[]", "[]"),
+            (true, "[]", "[]"),
+            (false, "{}", "{}"),
+            (true, "{}", "{}"),
+        ];
+
+        for (trailing_commas, input, expected) in cases {
+            let mut formatter = Formatter::new(Some(FormatOptions {
+                trailing_commas,
+                ..Default::default()
+            }));
+            assert_eq!(formatter.format(input), format!("{}\n", expected));
+        }
+    }
+
+    #[test]
+    fn test_format_nested_empty_containers() {
+        let cases = vec![
+            (
+                r#"{"a": {}, "b": [], "c": [{}, []]}"#,
+                "{\n    \"a\": {},\n    \"b\": [],\n    \"c\": [\n        {},\n        []\n    ]\n}",
+            ),
+            (
+                r#"[[[]], {"x": {"y": {}}}]"#,
+                "[\n    [\n        []\n    ],\n    {\n        \"x\": {\n            \"y\": {}\n        }\n    }\n]",
+            ),
+        ];
+
+        for (input, expected) in cases {
+            let mut formatter = Formatter::new(None);
+            assert_eq!(formatter.format(input), format!("{}\n", expected));
+        }
+    }
+
+    #[test]
+    fn test_format_sort_keys() {
+        let cases = vec![
+            (
+                FormatOptions::builder().sort_keys(true).build(),
+                r#"{"b": 1, "a": {"z": [3, 1, 2], "y": null}, "c": [{"k": 1, "j": 2}]}"#,
+                r#"{
+    "a": {
+        "y": null,
+        "z": [
+            3,
+            1,
+            2
+        ]
+    },
+    "b": 1,
+    "c": [
+        {
+            "j": 2,
+            "k": 1
+        }
+    ]
+}"#,
+            ),
+            (
+                FormatOptions::builder()
+                    .sort_keys(true)
+                    .compact_objects_max_members(3)
+                    .build(),
+                r#"{"b": 1, "a": 2, "B": 3}"#,
+                r#"{"B": 3, "a": 2, "b": 1}"#,
+            ),
+            (
+                FormatOptions::builder()
+                    .sort_keys(true)
+                    .tabular_arrays(true)
+                    .build(),
+                r#"[{"b": 1, "a": 2}, {"a": 10, "b": 20}]"#,
+                r#"[
+    {"a": 2,  "b": 1},
+    {"a": 10, "b": 20}
+]"#,
+            ),
+            (
+                FormatOptions::default(),
+                r#"{"b": 1, "a": 2}"#,
+                "{\n    \"b\": 1,\n    \"a\": 2\n}",
+            ),
+        ];
+
+        for (options, input, expected) in cases {
+            let mut formatter = Formatter::new(Some(options));
+            assert_eq!(formatter.format(input), format!("{}\n", expected));
+        }
+    }
+
+    #[test]
+    fn test_format_sort_type_then_keys() {
+        let input = r#"{"obj": {"z": [], "y": 1}, "list": [{"b": 1, "a": {}}], "s": "x", "b": true, "arr": [], "n": null, "a": {}}"#;
+        let expected = r#"{
+    "b": true,
+    "n": null,
+    "s": "x",
+    "arr": [],
+    "list": [
+        {
+            "b": 1,
+            "a": {}
+        }
+    ],
+    "a": {},
+    "obj": {
+        "y": 1,
+        "z": []
+    }
+}"#;
+        let mut formatter = Formatter::new(Some(
+            FormatOptions::builder()
+                .sort(SortMode::TypeThenKeys)
+                .build(),
+        ));
+        assert_eq!(formatter.format(input), format!("{}\n", expected));
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        let a = r#"{
+    "b": [1.0, 2E1, -0, "x"],
+    "a": {"y": null, "x": 1e-7},
+    "c": "\u00e9\n\u001f"
+}"#;
+        let b = r#"{"c":"é\n\u001F","a":{"x":0.0000001,"y":null},"b":[1,20,0,"x"]}"#;
+        let expected = "{\"a\":{\"x\":1e-7,\"y\":null},\"b\":[1,20,0,\"x\"],\"c\":\"é\\n\\u001f\"}";
+        assert_eq!(Formatter::canonicalize(a).unwrap(), expected);
+        assert_eq!(Formatter::canonicalize(b).unwrap(), expected);
+        // the last of repeated keys wins
+        assert_eq!(
+            Formatter::canonicalize(r#"{"a": 1, "a": 2}"#).unwrap(),
+            r#"{"a":2}"#
+        );
+
+        let error = Formatter::canonicalize(r#"{"a": }"#).unwrap_err();
+        assert_eq!(
+            (error.message.as_str(), error.offset),
+            ("Unexpected token of input", 6)
+        );
+    }
+
+    #[cfg(feature = "hash")]
+    #[test]
+    fn test_content_address() {
+        let address = Formatter::content_address(r#"{"b": [1.0, 2], "a": "x"}"#).unwrap();
+        let cases = vec![
+            r#"{"a":"x","b":[1,2]}"#,
+            "{\n    \"a\": \"x\",\n    \"b\": [\n        1,\n        2e0\n    ]\n}\n",
+            r#"{"b": [1, 2], "a": "\u0078"}"#,
+        ];
+        for input in cases {
+            assert_eq!(Formatter::content_address(input).unwrap(), address);
+        }
+        // SHA-256 of the canonical text
+        assert_eq!(
+            Formatter::content_address("[]").unwrap(),
+            "4f53cda18c2baa0c0354bb5f9a3ecbe5ed12ab4d8e11ba873c2f11161202b945"
+        );
+
+        let different = vec![r#"{"a":"x","b":[2,1]}"#, r#"{"a":"x","b":[1,2],"c":null}"#];
+        for input in different {
+            assert_ne!(Formatter::content_address(input).unwrap(), address);
+        }
+        assert!(Formatter::content_address("[1,").is_err());
+    }
+
+    #[test]
+    fn test_canonicalize_key_order() {
+        // keys compare by UTF-16 code units, putting the emoji's surrogate
+        // pair before U+FB33
+        let input = r#"{"\u20ac": 1, "\r": 2, "\ufb33": 3, "1": 4, "\ud83d\ude00": 5, "\u0080": 6, "\u00f6": 7}"#;
+        let expected = "{\"\\r\":2,\"1\":4,\"\u{80}\":6,\"ö\":7,\"€\":1,\"😀\":5,\"\u{fb33}\":3}";
+        assert_eq!(Formatter::canonicalize(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_canonical_number() {
+        // from the number samples of RFC 8785, appendix B
+        let cases = vec![
+            (0x0000000000000000, "0"),
+            (0x8000000000000000, "0"),
+            (0x0000000000000001, "5e-324"),
+            (0x8000000000000001, "-5e-324"),
+            (0x7fefffffffffffff, "1.7976931348623157e+308"),
+            (0xffefffffffffffff, "-1.7976931348623157e+308"),
+            (0x4340000000000000, "9007199254740992"),
+            (0xc340000000000000, "-9007199254740992"),
+            (0x4430000000000000, "295147905179352830000"),
+            (0x44b52d02c7e14af5, "9.999999999999997e+22"),
+            (0x44b52d02c7e14af6, "1e+23"),
+            (0x44b52d02c7e14af7, "1.0000000000000001e+23"),
+            (0x444b1ae4d6e2ef4e, "999999999999999700000"),
+            (0x444b1ae4d6e2ef4f, "999999999999999900000"),
+            (0x444b1ae4d6e2ef50, "1e+21"),
+            (0x3eb0c6f7a0b5ed8c, "9.999999999999997e-7"),
+            (0x3eb0c6f7a0b5ed8d, "0.000001"),
+            (0x41b3de4355555553, "333333333.3333332"),
+            (0xc380000000000000, "-144115188075855870"),
+            (0x7ff0000000000000, "null"),
+            (0x7fffffffffffffff, "null"),
+        ];
+
+        for (bits, expected) in cases {
+            let value = f64::from_bits(bits);
+            assert_eq!(canonical_number(value), expected, "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn test_to_string_pretty() {
+        let value = Value::Object(vec![
+            ("name".to_string(), Value::String("x".to_string())),
+            (
+                "tags".to_string(),
+                Value::Array(vec![Value::Number(1.0), Value::Null]),
+            ),
+        ]);
+        let cases = vec![
+            (
+                FormatOptions::default(),
+                "{\n    \"name\": \"x\",\n    \"tags\": [\n        1,\n        null\n    ]\n}",
+            ),
+            (
+                FormatOptions::builder()
+                    .spaces(2)
+                    .trailing_commas(true)
+                    .build(),
+                "{\n  \"name\": \"x\",\n  \"tags\": [\n    1,\n    null,\n  ],\n}",
+            ),
+        ];
+
+        for (options, expected) in cases {
+            let pretty = value.to_string_pretty(&options);
+            assert_eq!(pretty, expected);
+        }
+
+        let input = r#"{"a": [1.5, {"b": "c"}]}"#;
+        let value = Parser::new(input).parse().to_value();
+        let pretty = value.to_string_pretty(&FormatOptions::default());
+        assert_eq!(Parser::new(&pretty).parse().to_value(), value);
+    }
+
+    #[test]
+    fn test_format_semi_compact() {
+        let cases = vec![
+            (
+                r#"{"hello": "world"}"#,
+                "{\n    \"hello\":\"world\"\n}".to_string(),
+            ),
+            (
+                r#"{"hello": {"foo": 42}, "world": ["bar", 42]}"#,
+                "{\n    \"hello\":{\n        \"foo\":42\n    },\n    \"world\":[\n        \"bar\",\n        42\n    ]\n}".to_string(),
+            ),
+        ];
+
+        for (input, expected) in cases {
+            let mut formatter = Formatter::new(Some(FormatOptions {
+                colon_spacing: ColonSpacing::None,
+                ..Default::default()
+            }));
+            assert_eq!(formatter.format(input), format!("{}\n", expected));
+        }
+    }
+
+    #[test]
+    fn test_format_colon_spacing() {
+        let input = r#"{"a": {"b": 1}}"#;
+        let cases = vec![
+            (false, false, "{\n    \"a\":{\n        \"b\":1\n    }\n}"),
+            (false, true, "{\n    \"a\": {\n        \"b\": 1\n    }\n}"),
+            (true, false, "{\n    \"a\" :{\n        \"b\" :1\n    }\n}"),
+            (true, true, "{\n    \"a\" : {\n        \"b\" : 1\n    }\n}"),
+        ];
+
+        for (before, after, expected) in cases {
+            let options = FormatOptions::builder()
+                .space_before_colon(before)
+                .space_after_colon(after)
+                .build();
+            let mut formatter = Formatter::new(Some(options));
+            assert_eq!(formatter.format(input), format!("{}\n", expected));
+        }
+    }
+
+    #[test]
+    fn test_format_align_values() {
+        let input = r#"{"a": 1, "long": {"x": [1, 2], "yy": null}, "mid": "v", "e": {}}"#;
+        let cases = vec![
+            (
+                FormatOptions::builder().align_values(true).build(),
+                r#"{
+    "a"   : 1,
+    "long": {
+        "x" : [
+            1,
+            2
+        ],
+        "yy": null
+    },
+    "mid" : "v",
+    "e"   : {}
+}"#,
+            ),
+            (
+                FormatOptions::builder()
+                    .align_values(true)
+                    .space_before_colon(true)
+                    .build(),
+                r#"{
+    "a"    : 1,
+    "long" : {
+        "x"  : [
+            1,
+            2
+        ],
+        "yy" : null
+    },
+    "mid"  : "v",
+    "e"    : {}
+}"#,
+            ),
+        ];
+
+        for (options, expected) in cases {
+            let mut formatter = Formatter::new(Some(options));
+            assert_eq!(formatter.format(input), format!("{}\n", expected));
+        }
+    }
+
+    #[test]
+    fn test_find_long_lines() {
+        let input = r#"{"short": 1, "a_rather_long_key_name": "with an even longer string value"}"#;
+        let mut formatter = Formatter::new(None);
+        let formatted = formatter.format(input);
+
+        assert_eq!(find_long_lines(&formatted, 80), vec![]);
+        assert_eq!(
+            find_long_lines(&formatted, 40),
+            vec![(
+                3,
+                "    \"a_rather_long_key_name\": \"with an even longer string value\""
+            )]
+        );
+    }
+
+    #[test]
+    fn test_reindent() {
+        let input = "{\n  \"a\": 1, \"b\": [1,\n\t\t2],\n      \"c\": {\"d\": \"}\"\n},\n\n\"e\": null\n   }\n";
+        let expected = "{\n    \"a\": 1, \"b\": [1,\n        2],\n    \"c\": {\"d\": \"}\"\n    },\n\n    \"e\": null\n}\n";
+        let mut formatter = Formatter::new(None);
+        let reindented = formatter.reindent(input);
+        assert_eq!(reindented, expected);
+        assert_eq!(reindented.lines().count(), input.lines().count());
+    }
+
+    #[test]
+    fn test_format_json5() {
+        let cases = vec![
+            (
+                r#"{"hello": "world", "it's": "it's", "kebab-key": [1, true]}"#,
+                "{\n    hello: 'world',\n    'it\\'s': 'it\\'s',\n    'kebab-key': [\n        1,\n        true,\n    ],\n}".to_string(),
+            ),
+            (
+                r#"[{"_id": 1, "$ref": "x", "1st": null}]"#,
+                "[\n    {\n        _id: 1,\n        $ref: 'x',\n        '1st': null,\n    },\n]".to_string(),
+            ),
+        ];
+
+        for (input, expected) in cases {
+            let mut formatter = Formatter::new(Some(FormatOptions {
+                dialect: Dialect::Json5,
+                ..Default::default()
+            }));
+            assert_eq!(formatter.format(input), format!("{}\n", expected));
+        }
+    }
+
+    #[test]
+    fn test_format_with_source_map() {
+        let input = r#"{"a":[1,{"b":true}],"c":"x"}"#;
+        let mut formatter = Formatter::new(None);
+        let (output, mappings) = formatter.format_with_source_map(input).unwrap();
+        assert_eq!(output, formatter.format(input));
+
+        let mapping = mappings
+            .iter()
+            .find(|mapping| mapping.pointer == "/a/1/b")
+            .unwrap();
+        assert_eq!(mapping.input, 13..17);
+        assert_eq!(mapping.output, 51..55);
+        assert_eq!(&output[mapping.output.clone()], "true");
+        assert_eq!(mappings.len(), 6);
+        for mapping in &mappings {
+            let input_node = Parser::new(&input[mapping.input.clone()]).parse();
+            let output_node = Parser::new(&output[mapping.output.clone()]).parse();
+            assert_eq!(input_node, output_node, "{}", mapping.pointer);
+        }
+
+        // members move with their keys when sorted
+        let input = r#"{"b": 2, "a": 1}"#;
+        let mut formatter = Formatter::new(Some(FormatOptions::builder().sort_keys(true).build()));
+        let (output, mappings) = formatter.format_with_source_map(input).unwrap();
+        let mapping = &mappings[1];
+        assert_eq!(mapping.pointer, "/b");
+        assert_eq!(&input[mapping.input.clone()], "2");
+        assert_eq!(&output[mapping.output.clone()], "2");
+        assert!(mapping.output.start > mappings[2].output.start);
+
+        assert!(formatter.format_with_source_map("[1,").is_err());
+    }
+
+    #[test]
+    fn test_format_raw_pointers() {
+        let input = r#"{"matrix": [[1, 0],
+           [0, 1]], "name":"x", "list": [{"a":1}, {"a" :  2}], "a": {"a": [ 3 ]}}"#;
+        let mut formatter = Formatter::new(Some(
+            FormatOptions::builder()
+                .raw_pointers(vec!["/matrix".to_string(), "/list/1".to_string()])
+                .build(),
+        ));
+        assert_eq!(
+            formatter.format(input),
+            r#"{
+    "matrix": [[1, 0],
+           [0, 1]],
+    "name": "x",
+    "list": [
+        {
+            "a": 1
+        },
+        {"a" :  2}
+    ],
+    "a": {
+        "a": [
+            3
+        ]
+    }
+}
+"#
+        );
+
+        // the whole document, repeated keys each keeping their own text, and
+        // an element of what would otherwise be a table
         let cases = vec![
+            ("", "[1,2]", "[1,2]\n"),
             (
-                Node::new(SyntaxKind::StringLiteral("hello".to_string()), vec![]),
-                "\"hello\"".to_string(),
+                "/1",
+                r#"[{"a":1},{"a" : 2}]"#,
+                "[\n    {\n        \"a\": 1\n    },\n    {\"a\" : 2}\n]\n",
             ),
             (
-                Node::new(SyntaxKind::NumberLiteral(42.0), vec![]),
-                "42".to_string(),
+                "/a",
+                r#"{"a":[ 1 ],"a":[ 2 ]}"#,
+                "{\n    \"a\": [ 1 ],\n    \"a\": [ 2 ]\n}\n",
             ),
+        ];
+        for (pointer, input, expected) in cases {
+            let mut formatter = Formatter::new(Some(
+                FormatOptions::builder()
+                    .tabular_arrays(true)
+                    .raw_pointers(vec![pointer.to_string()])
+                    .build(),
+            ));
+            assert_eq!(formatter.format(input), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_format_comment_style() {
+        let input = "// settings
+{
+    // the name
+    \"name\": \"x\", // short
+    \"tags\": [
+        1, /* first */
+        2
+    ],
+    \"empty\": {
+        // nothing yet
+    }
+}";
+        let format = |comment_style| {
+            let mut parser = Parser::with_options(
+                input,
+                ParseOptions {
+                    allow_comments: true,
+                    spans: true,
+                    ..Default::default()
+                },
+            );
+            let node = parser.parse();
+            let comments = parser.comments();
+            let mut formatter = Formatter::new(Some(
+                FormatOptions::builder()
+                    .dialect(Dialect::Json5)
+                    .comment_style(comment_style)
+                    .build(),
+            ));
+            formatter.format_node_with_comments(&node, &comments)
+        };
+
+        let cases = vec![
             (
-                Node::new(SyntaxKind::TrueKeyword, vec![]),
-                "true".to_string(),
+                CommentStyle::Keep,
+                "// settings
+{
+    // the name
+    name: 'x', // short
+    tags: [
+        1, /* first */
+        2,
+    ],
+    empty: {
+        // nothing yet
+    },
+}",
             ),
             (
-                Node::new(SyntaxKind::FalseKeyword, vec![]),
-                "false".to_string(),
+                CommentStyle::Block,
+                "/* settings */
+{
+    /* the name */
+    name: 'x', /* short */
+    tags: [
+        1, /* first */
+        2,
+    ],
+    empty: {
+        /* nothing yet */
+    },
+}",
             ),
             (
-                Node::new(SyntaxKind::NullKeyword, vec![]),
-                "null".to_string(),
+                CommentStyle::Line,
+                "// settings
+{
+    // the name
+    name: 'x', // short
+    tags: [
+        1, // first
+        2,
+    ],
+    empty: {
+        // nothing yet
+    },
+}",
             ),
         ];
+        for (comment_style, expected) in cases {
+            assert_eq!(format(comment_style), expected, "{:?}", comment_style);
+        }
 
-        for (node, expected) in cases {
-            let formatter = Formatter::new(None);
-            assert_eq!(formatter.format_primitive(&node), expected);
+        let mut formatter = Formatter::new(Some(
+            FormatOptions::builder()
+                .comment_style(CommentStyle::Line)
+                .build(),
+        ));
+        let lines: Vec<String> = ["/**\n * one\n *\n * two\n */", "/**/", "// a */ b"]
+            .iter()
+            .map(|text| formatter.comment_lines(text).join("\n"))
+            .collect();
+        assert_eq!(lines, vec!["// one\n//\n// two", "//", "// a */ b"]);
+        formatter.options.comment_style = CommentStyle::Block;
+        assert_eq!(formatter.comment_lines("// a */ b"), vec!["// a */ b"]);
+    }
+
+    #[test]
+    fn test_format_to_with_progress() {
+        let node = Parser::new(r#"{"hello": [1, 2]}"#).parse();
+        let mut formatter = Formatter::new(None);
+        let mut output = Vec::new();
+        let mut reports = Vec::new();
+        formatter
+            .format_to_with_progress(&node, &mut output, |progress| reports.push(progress))
+            .unwrap();
+
+        let expected = "{\n    \"hello\": [\n        1,\n        2\n    ]\n}";
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+        assert_eq!(reports.len(), 6);
+        assert_eq!(
+            reports.last(),
+            Some(&Progress {
+                lines: 6,
+                bytes: expected.len()
+            })
+        );
+
+        // whether written a child at a time or whole, the output is what
+        // format_node gives
+        let inputs = vec![
+            r#"{"b": [1, 2], "a": {"c": "d"}, "list": [{"x": 1}, {"x": 2}]}"#,
+            r#"[1, 2, 3, "a much longer string that does not fit within the width"]"#,
+            r#"[1, 2, 3]"#,
+            r#"{"id": 1, "name": "x"}"#,
+            r#"[{"a": 1, "b": 2}, {"a": 3, "b": 4}]"#,
+            r#"[[1, 2], [3, 4]]"#,
+        ];
+        let options = vec![
+            FormatOptions::default(),
+            FormatOptions::builder().print_width(40).build(),
+            FormatOptions::builder().print_width(10).build(),
+            FormatOptions::builder()
+                .align_values(true)
+                .sort_keys(true)
+                .print_width(40)
+                .build(),
+            FormatOptions::builder()
+                .trailing_commas(true)
+                .line_ending(LineEnding::Crlf)
+                .build(),
+            FormatOptions::builder()
+                .compact_objects_max_members(2)
+                .build(),
+            FormatOptions::builder().tabular_arrays(true).build(),
+            FormatOptions::builder()
+                .compact_primitive_arrays(true)
+                .inline_single_element(true)
+                .build(),
+        ];
+        for input in inputs {
+            let node = Parser::new(input).parse();
+            for options in &options {
+                let expected = Formatter::new(Some(options.clone())).format_node(&node);
+                let mut output = Vec::new();
+                let mut last = Progress::default();
+                Formatter::new(Some(options.clone()))
+                    .format_to_with_progress(&node, &mut output, |progress| last = progress)
+                    .unwrap();
+                assert_eq!(String::from_utf8(output).unwrap(), expected, "{}", input);
+                assert_eq!(
+                    last,
+                    Progress {
+                        lines: expected.split_inclusive('\n').count(),
+                        bytes: expected.len()
+                    }
+                );
+            }
         }
     }
 
     #[test]
-    fn test_format_array() {
+    fn test_detect_line_ending() {
         let cases = vec![
+            ("{\n    \"a\": 1\n}\n", LineEnding::Lf),
+            ("{\r\n    \"a\": 1\r\n}\r\n", LineEnding::Crlf),
             (
-                Node::new(
-                    SyntaxKind::ArrayLiteralExpression,
-                    vec![
-                        Node::new(SyntaxKind::NumberLiteral(1.0), vec![]),
-                        Node::new(SyntaxKind::NumberLiteral(2.0), vec![]),
-                    ],
-                ),
-                "[\n    1,\n    2\n]".to_string(),
-                "[\n    1,\n    2,\n]".to_string(),
-            ),
-            (
-                Node::new(
-                    SyntaxKind::ArrayLiteralExpression,
-                    vec![
-                        Node::new(
-                            SyntaxKind::ArrayLiteralExpression,
-                            vec![Node::new(SyntaxKind::NumberLiteral(1.0), vec![])],
-                        ),
-                        Node::new(
-                            SyntaxKind::ArrayLiteralExpression,
-                            vec![Node::new(SyntaxKind::NumberLiteral(2.0), vec![])],
-                        ),
-                    ],
-                ),
-                "[\n    [\n        1\n    ],\n    [\n        2\n    ]\n]".to_string(),
-                "[\n    [\n        1,\n    ],\n    [\n        2,\n    ],\n]".to_string(),
+                "{\r\n    \"a\": 1,\r\n    \"b\": 2\n}\r\n",
+                LineEnding::Crlf,
             ),
+            ("{\r\n    \"a\": 1,\n    \"b\": 2\n}", LineEnding::Lf),
+            ("{\"a\": 1}", LineEnding::Lf),
         ];
 
-        for (node, expected, expected_with_trailing_commas) in cases {
-            let mut formatter = Formatter::new(None);
-            assert_eq!(formatter.format_array(&node), expected);
-            formatter.options.trailing_commas = true;
-            assert_eq!(formatter.format_array(&node), expected_with_trailing_commas);
+        for (input, expected) in cases {
+            assert_eq!(detect_line_ending(input), expected);
         }
     }
 
     #[test]
-    fn test_format_object() {
+    fn test_format_crlf() {
+        let mut formatter = Formatter::new(Some(FormatOptions {
+            line_ending: LineEnding::Crlf,
+            ..Default::default()
+        }));
+        assert_eq!(
+            formatter.format(r#"{"hello": [1]}"#),
+            "{\r\n    \"hello\": [\r\n        1\r\n    ]\r\n}\r\n"
+        );
+    }
+
+    #[test]
+    fn test_format_final_newline() {
+        let cases = vec![
+            (true, "[1]", "[\n    1\n]\n"),
+            (true, "[1]\n", "[\n    1\n]\n"),
+            (true, "[1]\n\n", "[\n    1\n]\n"),
+            (false, "[1]", "[\n    1\n]"),
+            (false, "[1]\n", "[\n    1\n]"),
+        ];
+
+        for (final_newline, input, expected) in cases {
+            let mut formatter = Formatter::new(Some(
+                FormatOptions::builder()
+                    .final_newline(final_newline)
+                    .build(),
+            ));
+            assert_eq!(formatter.format(input), expected);
+        }
+
+        let formatter = Formatter::new(None);
+        let cases = vec![("[]", "[]\n"), ("[]\n", "[]\n")];
+        for (formatted, expected) in cases {
+            let mut formatted = formatted.to_string();
+            formatter.push_final_newline(&mut formatted);
+            assert_eq!(formatted, expected);
+        }
+    }
+
+    #[test]
+    fn test_format_deep_nesting_inline_layouts() {
+        // the innermost container never fits on one line, so every level
+        // tries and rejects an inline layout on the way out
+        let depth = 64;
+        let arrays = format!("{}[1, 2]{}", "[".repeat(depth), "]".repeat(depth));
+        let objects = format!(
+            "{}{{\"a\": 1, \"b\": 2, \"c\": 3}}{}",
+            "{\"a\": ".repeat(depth),
+            "}".repeat(depth)
+        );
         let cases = vec![
             (
-                Node::new(
-                    SyntaxKind::ObjectLiteralExpression,
-                    vec![
-                        Node::new(
-                            SyntaxKind::PropertyAssignment,
-                            vec![
-                                Node::new(SyntaxKind::StringLiteral("hello".to_string()), vec![]),
-                                Node::new(SyntaxKind::StringLiteral("world".to_string()), vec![]),
-                            ],
-                        ),
-                    ],
-                ),
-                "{\n    \"hello\": \"world\"\n}".to_string(),
-                "{\n    \"hello\": \"world\",\n}".to_string(),
+                &arrays,
+                FormatOptions::builder().inline_single_element(true),
             ),
+            (&arrays, FormatOptions::builder().print_width(1)),
             (
-                Node::new(
-                    SyntaxKind::ObjectLiteralExpression,
-                    vec![
-                        Node::new(
-                            SyntaxKind::PropertyAssignment,
-                            vec![
-                                Node::new(SyntaxKind::StringLiteral("hello".to_string()), vec![]),
-                                Node::new(
-                                    SyntaxKind::ObjectLiteralExpression,
-                                    vec![
-                                        Node::new(
-                                            SyntaxKind::PropertyAssignment,
-                                            vec![
-                                                Node::new(SyntaxKind::StringLiteral("foo".to_string()), vec![]),
-                                                Node::new(SyntaxKind::NumberLiteral(42.0), vec![]),
-                                            ],
-                                        ),
-                                    ],
-                                ),
-                            ],
-                        ),
-                        Node::new(
-                            SyntaxKind::PropertyAssignment,
-                            vec![
-                                Node::new(SyntaxKind::StringLiteral("world".to_string()), vec![]),
-                                Node::new(
-                                    SyntaxKind::ObjectLiteralExpression,
-                                    vec![
-                                        Node::new(
-                                            SyntaxKind::PropertyAssignment,
-                                            vec![
-                                                Node::new(SyntaxKind::StringLiteral("bar".to_string()), vec![]),
-                                                Node::new(SyntaxKind::NumberLiteral(42.0), vec![]),
-                                            ],
-                                        ),
-                                    ],
-                                ),
-                            ],
-                        ),
-                    ],
-                ),
-                "{\n    \"hello\": {\n        \"foo\": 42\n    },\n    \"world\": {\n        \"bar\": 42\n    }\n}".to_string(),
-                "{\n    \"hello\": {\n        \"foo\": 42,\n    },\n    \"world\": {\n        \"bar\": 42,\n    },\n}".to_string(),
+                &objects,
+                FormatOptions::builder().inline_single_element(true),
+            ),
+            (&objects, FormatOptions::builder().print_width(1)),
+            (
+                &objects,
+                FormatOptions::builder().compact_objects_max_members(2),
             ),
         ];
 
-        for (node, expected, expected_with_trailing_commas) in cases {
-            let mut formatter = Formatter::new(None);
-            assert_eq!(formatter.format_object(&node), expected);
-            formatter.options.trailing_commas = true;
+        for (input, options) in cases {
+            let expected = Formatter::new(None).format(input);
             assert_eq!(
-                formatter.format_object(&node),
-                expected_with_trailing_commas
+                Formatter::new(Some(options.build())).format(input),
+                expected
             );
         }
     }
 
     #[test]
-    fn test_format_node() {
+    fn test_format_inline_single_element() {
         let cases = vec![
+            (r#"[1]"#, "[1]"),
+            (r#"{"a": 1}"#, "{\"a\": 1}"),
+            (r#"{"a": [{"b": true}]}"#, "{\"a\": [{\"b\": true}]}"),
+            (r#"[1, 2]"#, "[\n    1,\n    2\n]"),
             (
-                Node::new(
-                    SyntaxKind::ArrayLiteralExpression,
-                    vec![
-                        Node::new(SyntaxKind::StringLiteral("hello".to_string()), vec![]),
-                        Node::new(SyntaxKind::NumberLiteral(42.0), vec![]),
-                    ],
-                ),
-                "[\n    \"hello\",\n    42\n]".to_string(),
+                r#"{"a": [1], "b": {"c": [2, 3]}}"#,
+                "{\n    \"a\": [1],\n    \"b\": {\n        \"c\": [\n            2,\n            3\n        ]\n    }\n}",
             ),
+        ];
+
+        for (input, expected) in cases {
+            let mut formatter = Formatter::new(Some(FormatOptions {
+                inline_single_element: true,
+                ..Default::default()
+            }));
+            assert_eq!(formatter.format(input), format!("{}\n", expected));
+        }
+    }
+
+    #[test]
+    fn test_find_mixed_indentation() {
+        let cases = vec![
+            ("{\n    \"a\": [\n        1\n    ]\n}", vec![]),
+            ("{\n\t\"a\": [\n\t\t1\n\t]\n}", vec![]),
+            ("{\n    \"a\": [\n\t\t1\n    ]\n}", vec![3]),
+            ("{\n\t\"a\": [\n\t    1\n  \t]\n}", vec![3, 4]),
+            ("{\"a\": 1}", vec![]),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(find_mixed_indentation(input), expected);
+        }
+    }
+
+    #[test]
+    fn test_format_compact_objects() {
+        let cases = vec![
+            (r#"{"x": 1, "y": 2}"#, "{\"x\": 1, \"y\": 2}"),
             (
-                Node::new(
-                    SyntaxKind::ObjectLiteralExpression,
-                    vec![
-                        Node::new(
-                            SyntaxKind::PropertyAssignment,
-                            vec![
-                                Node::new(SyntaxKind::StringLiteral("hello".to_string()), vec![]),
-                                Node::new(SyntaxKind::ObjectLiteralExpression, vec![
-                                    Node::new(
-                                        SyntaxKind::PropertyAssignment,
-                                        vec![
-                                            Node::new(SyntaxKind::StringLiteral("foo".to_string()), vec![]),
-                                            Node::new(SyntaxKind::NumberLiteral(42.0), vec![]),
-                                        ],
-                                    ),
-                                ]),
-                            ],
-                        ),
-                        Node::new(
-                            SyntaxKind::PropertyAssignment,
-                            vec![
-                                Node::new(SyntaxKind::StringLiteral("world".to_string()), vec![]),
-                                Node::new(SyntaxKind::ArrayLiteralExpression, vec![
-                                    Node::new(SyntaxKind::StringLiteral("bar".to_string()), vec![]),
-                                    Node::new(SyntaxKind::NumberLiteral(42.0), vec![]),
-                                ]),
-                            ],
-                        ),
-                    ],
-                ),
-                "{\n    \"hello\": {\n        \"foo\": 42\n    },\n    \"world\": [\n        \"bar\",\n        42\n    ]\n}".to_string(),
+                r#"{"x": 1, "y": 2, "z": 3}"#,
+                "{\n    \"x\": 1,\n    \"y\": 2,\n    \"z\": 3\n}",
+            ),
+            (
+                r#"[{"x": 1, "y": 2}, {"x": 3}]"#,
+                "[\n    {\"x\": 1, \"y\": 2},\n    {\"x\": 3}\n]",
+            ),
+            (
+                r#"{"x": [1], "y": 2}"#,
+                "{\n    \"x\": [\n        1\n    ],\n    \"y\": 2\n}",
+            ),
+            // few enough members, but too wide for 80 columns
+            (
+                r#"{"description": "a fairly long sentence that goes on and on", "summary": "and on"}"#,
+                "{\n    \"description\": \"a fairly long sentence that goes on and on\",\n    \"summary\": \"and on\"\n}",
+            ),
+            // the key in front counts towards the width
+            (
+                r#"{"a_rather_long_key_name_here": {"x": "some text here", "y": "more text here"}, "b": 1, "c": 2}"#,
+                "{\n    \"a_rather_long_key_name_here\": {\n        \"x\": \"some text here\",\n        \"y\": \"more text here\"\n    },\n    \"b\": 1,\n    \"c\": 2\n}",
             ),
         ];
 
-        for (node, expected) in cases {
-            let mut formatter = Formatter::new(None);
-            assert_eq!(formatter.format_node(&node), expected);
+        for (input, expected) in cases {
+            let mut formatter = Formatter::new(Some(
+                FormatOptions::builder()
+                    .compact_objects_max_members(2)
+                    .build(),
+            ));
+            assert_eq!(formatter.format(input), format!("{}\n", expected));
         }
+
+        // print_width overrides the 80 columns
+        let mut formatter = Formatter::new(Some(
+            FormatOptions::builder()
+                .compact_objects_max_members(2)
+                .print_width(20)
+                .build(),
+        ));
+        assert_eq!(
+            formatter.format(r#"{"x": "abc", "y": "def"}"#),
+            "{\n    \"x\": \"abc\",\n    \"y\": \"def\"\n}\n"
+        );
     }
 
     #[test]
-    fn test_format() {
+    fn test_format_tabular_arrays() {
         let cases = vec![
             (
-                r#"{"hello": "world"}"#,
-                "{\n    \"hello\": \"world\"\n}".to_string(),
+                r#"[{"id": 1, "name": "alice", "admin": true}, {"id": 20, "name": "bob", "admin": false}]"#,
+                "[\n    {\"id\": 1,  \"name\": \"alice\", \"admin\": true},\n    {\"id\": 20, \"name\": \"bob\",   \"admin\": false}\n]",
             ),
             (
-                r#"[1, 2, 3]"#,
-                "[\n    1,\n    2,\n    3\n]".to_string(),
+                r#"[{"id": 1}, {"name": "bob"}]"#,
+                "[\n    {\n        \"id\": 1\n    },\n    {\n        \"name\": \"bob\"\n    }\n]",
             ),
             (
-                r#"{"hello": {"foo": 42}, "world": ["bar", 42]}"#,
-                "{\n    \"hello\": {\n        \"foo\": 42\n    },\n    \"world\": [\n        \"bar\",\n        42\n    ]\n}".to_string(),
+                r#"[{"id": [1]}, {"id": [2]}]"#,
+                "[\n    {\n        \"id\": [\n            1\n        ]\n    },\n    {\n        \"id\": [\n            2\n        ]\n    }\n]",
             ),
-            (
-                r#"[{"hello": "world"}, {"foo": "bar"}]"#,
-                "[\n    {\n        \"hello\": \"world\"\n    },\n    {\n        \"foo\": \"bar\"\n    }\n]".to_string(),
-            )
         ];
 
         for (input, expected) in cases {
-            let mut formatter = Formatter::new(None);
-            assert_eq!(formatter.format(&input), expected);
+            let mut formatter =
+                Formatter::new(Some(FormatOptions::builder().tabular_arrays(true).build()));
+            assert_eq!(formatter.format(input), format!("{}\n", expected));
+        }
+    }
+
+    #[test]
+    fn test_format_bracket_spacing() {
+        let input = r#"{"point": {"x": 1, "y": 2}, "list": [1], "empty": {}}"#;
+        let cases = vec![
+            (
+                false,
+                false,
+                "{\n    \"point\": {\"x\": 1, \"y\": 2},\n    \"list\": [1],\n    \"empty\": {}\n}",
+            ),
+            (
+                true,
+                false,
+                "{\n    \"point\": { \"x\": 1, \"y\": 2 },\n    \"list\": [1],\n    \"empty\": {}\n}",
+            ),
+            (
+                true,
+                true,
+                "{\n    \"point\": { \"x\": 1, \"y\": 2 },\n    \"list\": [ 1 ],\n    \"empty\": {}\n}",
+            ),
+        ];
+
+        for (bracket_spacing, array_bracket_spacing, expected) in cases {
+            let mut formatter = Formatter::new(Some(
+                FormatOptions::builder()
+                    .inline_single_element(true)
+                    .compact_objects_max_members(2)
+                    .bracket_spacing(bracket_spacing)
+                    .array_bracket_spacing(array_bracket_spacing)
+                    .build(),
+            ));
+            assert_eq!(formatter.format(input), format!("{}\n", expected));
         }
     }
 }
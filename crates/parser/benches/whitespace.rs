@@ -0,0 +1,81 @@
+//! Lexes a multi-megabyte indented document and the same document
+//! minified, to show how much of the time goes to skipping whitespace.
+//!
+//! Run with `cargo bench -p json-parser --bench whitespace`.
+
+use std::time::{Duration, Instant};
+
+use json_parser::lexer::Lexer;
+
+const ITERATIONS: u32 = 20;
+
+fn document(records: usize, indent: &str, newline: &str) -> String {
+    let field = |depth: usize, key: &str, value: &str| {
+        format!("{}{}\"{}\": {}", newline, indent.repeat(depth), key, value)
+    };
+    let mut doc = format!("[{}", newline);
+    for i in 0..records {
+        if i > 0 {
+            doc.push(',');
+            doc.push_str(newline);
+        }
+        doc.push_str(indent);
+        doc.push('{');
+        doc.push_str(&field(2, "id", &i.to_string()));
+        doc.push(',');
+        doc.push_str(&field(2, "name", "\"record\""));
+        doc.push(',');
+        doc.push_str(&field(2, "tags", "[true, false, null]"));
+        doc.push(',');
+        doc.push_str(&field(2, "score", "12.5"));
+        doc.push_str(newline);
+        doc.push_str(indent);
+        doc.push('}');
+    }
+    doc.push_str(newline);
+    doc.push(']');
+    doc
+}
+
+/// The fastest of `ITERATIONS` passes, which is the least disturbed by
+/// whatever else the machine is doing.
+fn time_lexing(input: &str) -> (Duration, usize) {
+    let mut tokens = 0;
+    let mut fastest = Duration::MAX;
+    for _ in 0..ITERATIONS {
+        let start = Instant::now();
+        tokens = Lexer::new(input).count();
+        fastest = fastest.min(start.elapsed());
+    }
+    (fastest, tokens)
+}
+
+fn main() {
+    let records = 40_000;
+    let indented = document(records, "        ", "\n");
+    let minified = document(records, "", "");
+    let deep = document(records, &" ".repeat(64), "\n");
+
+    let mut results = Vec::new();
+    for (name, input) in [
+        ("minified", &minified),
+        ("indented", &indented),
+        ("deep", &deep),
+    ] {
+        let (elapsed, tokens) = time_lexing(input);
+        let megabytes = input.len() as f64 / 1_000_000.0;
+        println!(
+            "{:>8}: {:6.2} MB, {} tokens, {:8.2?} per pass, {:7.1} MB/s",
+            name,
+            megabytes,
+            tokens,
+            elapsed,
+            megabytes / elapsed.as_secs_f64()
+        );
+        results.push((tokens, elapsed));
+    }
+    assert!(
+        results.iter().all(|(tokens, _)| *tokens == results[0].0),
+        "all documents lex alike"
+    );
+}
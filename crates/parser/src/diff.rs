@@ -0,0 +1,194 @@
+use std::{collections::BTreeSet, fmt};
+
+use crate::node::{escape_pointer_token, Node, SyntaxKind};
+use crate::parse::Parser;
+
+/// One difference between two documents. `before` is `None` for an added
+/// path and `after` is `None` for a removed one.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Change {
+    /// JSON pointer to the changed value.
+    pub path: String,
+    pub before: Option<Node>,
+    pub after: Option<Node>,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (&self.before, &self.after) {
+            (None, Some(after)) => write!(f, "+ {}: {}", self.path, after),
+            (Some(before), None) => write!(f, "- {}: {}", self.path, before),
+            (Some(before), Some(after)) => {
+                write!(f, "~ {}: {} -> {}", self.path, before, after)
+            }
+            (None, None) => write!(f, "  {}", self.path),
+        }
+    }
+}
+
+/// Parses `a` and `b` and lists what changed between them, see `diff_nodes`.
+///
+/// # Panics
+///
+/// Panics if either input fails to parse; use `Parser::try_parse` and
+/// `diff_nodes` to handle errors.
+pub fn diff(a: &str, b: &str) -> Vec<Change> {
+    diff_nodes(&Parser::new(a).parse(), &Parser::new(b).parse())
+}
+
+/// Lists the added, removed and changed paths between `old` and `new`.
+/// Objects are compared key by key, the last of repeated keys counting and
+/// each key listed once, and arrays by index; trailing removed elements are
+/// listed from the back.
+pub fn diff_nodes(old: &Node, new: &Node) -> Vec<Change> {
+    let mut changes = Vec::new();
+    diff_into(old, new, "", &mut changes);
+    changes
+}
+
+fn diff_into(old: &Node, new: &Node, path: &str, changes: &mut Vec<Change>) {
+    if old == new {
+        return;
+    }
+    match (&old.kind, &new.kind) {
+        (SyntaxKind::ObjectLiteralExpression, SyntaxKind::ObjectLiteralExpression) => {
+            let old_keys = unique_keys(old);
+            let new_keys = unique_keys(new);
+            for key in &old_keys {
+                let child_path = format!("{}/{}", path, escape_pointer_token(key));
+                match new.get(key) {
+                    Some(new_value) => {
+                        diff_into(old.get(key).unwrap(), new_value, &child_path, changes)
+                    }
+                    None => changes.push(removed(child_path, old.get(key).unwrap())),
+                }
+            }
+            for key in new_keys.iter().filter(|key| !old_keys.contains(key)) {
+                let child_path = format!("{}/{}", path, escape_pointer_token(key));
+                changes.push(added(child_path, new.get(key).unwrap()));
+            }
+        }
+        (SyntaxKind::ArrayLiteralExpression, SyntaxKind::ArrayLiteralExpression) => {
+            let common = old.children.len().min(new.children.len());
+            for index in 0..common {
+                let child_path = format!("{}/{}", path, index);
+                diff_into(
+                    &old.children[index],
+                    &new.children[index],
+                    &child_path,
+                    changes,
+                );
+            }
+            // remove from the back so earlier indices stay valid
+            for index in (common..old.children.len()).rev() {
+                let child_path = format!("{}/{}", path, index);
+                changes.push(removed(child_path, &old.children[index]));
+            }
+            for index in common..new.children.len() {
+                let child_path = format!("{}/{}", path, index);
+                changes.push(added(child_path, &new.children[index]));
+            }
+        }
+        _ => changes.push(Change {
+            path: path.to_string(),
+            before: Some(old.clone()),
+            after: Some(new.clone()),
+        }),
+    }
+}
+
+/// The keys of `node` in order of first appearance, each once.
+fn unique_keys(node: &Node) -> Vec<&str> {
+    let mut seen = BTreeSet::new();
+    node.keys()
+        .into_iter()
+        .filter(|key| seen.insert(*key))
+        .collect()
+}
+
+fn added(path: String, value: &Node) -> Change {
+    Change {
+        path,
+        before: None,
+        after: Some(value.clone()),
+    }
+}
+
+fn removed(path: String, value: &Node) -> Change {
+    Change {
+        path,
+        before: Some(value.clone()),
+        after: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff() {
+        let cases = vec![
+            (r#"{"a": 1}"#, r#"{"a": 1}"#, vec![]),
+            (
+                r#"{"a": 1}"#,
+                r#"{"a": 1, "b": [true]}"#,
+                vec!["+ /b: [true]"],
+            ),
+            (
+                r#"{"a": 1, "b": {"c": null}}"#,
+                r#"{"a": 1}"#,
+                vec![r#"- /b: {"c":null}"#],
+            ),
+            (
+                r#"{"a": {"b": "x"}}"#,
+                r#"{"a": {"b": "y"}}"#,
+                vec![r#"~ /a/b: "x" -> "y""#],
+            ),
+            (
+                r#"{"list": [1, 2, 3], "a/b": 1}"#,
+                r#"{"list": [1, 5], "a/b": 1, "c": 2}"#,
+                vec!["~ /list/1: 2 -> 5", "- /list/2: 3", "+ /c: 2"],
+            ),
+            (r#"[1]"#, r#"[1, 2, 3]"#, vec!["+ /1: 2", "+ /2: 3"]),
+            (r#"{"a": 1}"#, r#"[1]"#, vec![r#"~ : {"a":1} -> [1]"#]),
+            (
+                r#"{"a": {"x": 1}, "b": 1, "a": {"x": 2}, "b": 2}"#,
+                r#"{"a": {}, "c": 1, "c": 2}"#,
+                vec![r#"- /a/x: 2"#, "- /b: 2", "+ /c: 2"],
+            ),
+            (r#"{"a": 1, "a": 2}"#, r#"{"a": 2}"#, vec![]),
+        ];
+
+        for (a, b, expected) in cases {
+            let changes: Vec<String> = diff(a, b).iter().map(Change::to_string).collect();
+            assert_eq!(changes, expected, "{} {}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_diff_values() {
+        let changes = diff(r#"{"a": 1, "b": 2}"#, r#"{"a": 3, "c": 4}"#);
+        let number = |input: &str| Some(Parser::new(input).parse());
+        assert_eq!(
+            changes,
+            vec![
+                Change {
+                    path: "/a".to_string(),
+                    before: number("1"),
+                    after: number("3"),
+                },
+                Change {
+                    path: "/b".to_string(),
+                    before: number("2"),
+                    after: None,
+                },
+                Change {
+                    path: "/c".to_string(),
+                    before: None,
+                    after: number("4"),
+                },
+            ]
+        );
+    }
+}
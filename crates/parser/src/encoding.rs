@@ -0,0 +1,184 @@
+use crate::{node::Node, parse::Parser};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Latin1,
+}
+
+impl Encoding {
+    /// None for Latin-1, which encoding_rs only knows as windows-1252 as the
+    /// WHATWG spec has it, decoding 0x80-0x9F to other characters.
+    fn to_encoding_rs(self) -> Option<&'static encoding_rs::Encoding> {
+        match self {
+            Encoding::Utf8 => Some(encoding_rs::UTF_8),
+            Encoding::Utf16Le => Some(encoding_rs::UTF_16LE),
+            Encoding::Utf16Be => Some(encoding_rs::UTF_16BE),
+            Encoding::Latin1 => None,
+        }
+    }
+
+    fn bom(self) -> &'static [u8] {
+        match self {
+            Encoding::Utf8 => &[0xEF, 0xBB, 0xBF],
+            Encoding::Utf16Le => &[0xFF, 0xFE],
+            Encoding::Utf16Be => &[0xFE, 0xFF],
+            Encoding::Latin1 => &[],
+        }
+    }
+}
+
+/// Detects the encoding from a leading byte order mark, if any.
+pub fn detect_bom(bytes: &[u8]) -> Option<Encoding> {
+    match encoding_rs::Encoding::for_bom(bytes) {
+        Some((encoding, _)) if encoding == encoding_rs::UTF_8 => Some(Encoding::Utf8),
+        Some((encoding, _)) if encoding == encoding_rs::UTF_16LE => Some(Encoding::Utf16Le),
+        Some((encoding, _)) if encoding == encoding_rs::UTF_16BE => Some(Encoding::Utf16Be),
+        _ => None,
+    }
+}
+
+/// Transcodes `bytes` to UTF-8, stripping a matching byte order mark.
+/// Latin-1 bytes each become the character with that code point.
+pub fn decode(bytes: &[u8], encoding: Encoding) -> Result<String, String> {
+    let Some(encoding_rs) = encoding.to_encoding_rs() else {
+        return Ok(bytes.iter().map(|&byte| char::from(byte)).collect());
+    };
+    let (text, had_errors) = encoding_rs.decode_with_bom_removal(bytes);
+    if had_errors {
+        return Err(format!("Malformed {:?} input", encoding));
+    }
+    Ok(text.into_owned())
+}
+
+/// Transcodes `text` back to `encoding`, the inverse of `decode`, starting
+/// with a byte order mark when `bom` is set. Fails on characters Latin-1
+/// cannot hold.
+pub fn encode(text: &str, encoding: Encoding, bom: bool) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::with_capacity(text.len() + 3);
+    if bom {
+        bytes.extend_from_slice(encoding.bom());
+    }
+    match encoding {
+        Encoding::Utf8 => bytes.extend_from_slice(text.as_bytes()),
+        Encoding::Utf16Le => bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes)),
+        Encoding::Utf16Be => bytes.extend(text.encode_utf16().flat_map(u16::to_be_bytes)),
+        Encoding::Latin1 => {
+            for c in text.chars() {
+                match u8::try_from(c) {
+                    Ok(byte) => bytes.push(byte),
+                    Err(_) => return Err(format!("{:?} cannot be encoded as Latin-1", c)),
+                }
+            }
+        }
+    }
+    Ok(bytes)
+}
+
+pub fn from_bytes_with_encoding(bytes: &[u8], encoding: Encoding) -> Result<Node, String> {
+    let input = decode(bytes, encoding)?;
+    let node = Parser::new(&input).try_parse().map_err(|e| e.to_string())?;
+    Ok(node)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::SyntaxKind;
+
+    fn utf16le(input: &str) -> Vec<u8> {
+        input
+            .encode_utf16()
+            .flat_map(|unit| unit.to_le_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn test_detect_bom() {
+        let cases = vec![
+            (vec![0xEF, 0xBB, 0xBF, b'['], Some(Encoding::Utf8)),
+            (vec![0xFF, 0xFE, b'[', 0x00], Some(Encoding::Utf16Le)),
+            (vec![0xFE, 0xFF, 0x00, b'['], Some(Encoding::Utf16Be)),
+            (vec![b'[', b']'], None),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(detect_bom(&input), expected);
+        }
+    }
+
+    #[test]
+    fn test_from_bytes_with_utf16le() {
+        let mut input = vec![0xFF, 0xFE];
+        input.extend(utf16le("{\"name\": \"caf\u{e9}\"}"));
+        let node = from_bytes_with_encoding(&input, Encoding::Utf16Le).unwrap();
+        assert_eq!(
+            node.children[0].children[1].kind,
+            SyntaxKind::StringLiteral("caf\u{e9}".to_string())
+        );
+    }
+
+    #[test]
+    fn test_from_bytes_with_latin1() {
+        let input = b"{\"name\": \"caf\xE9\"}";
+        let node = from_bytes_with_encoding(input, Encoding::Latin1).unwrap();
+        assert_eq!(
+            node.children[0].children[1].kind,
+            SyntaxKind::StringLiteral("caf\u{e9}".to_string())
+        );
+    }
+
+    #[test]
+    fn test_latin1_is_not_windows_1252() {
+        // windows-1252 would read these as the euro sign and a low quote
+        assert_eq!(
+            decode(&[0x80, 0x82, 0xFF], Encoding::Latin1),
+            Ok("\u{80}\u{82}\u{ff}".to_string())
+        );
+    }
+
+    #[test]
+    fn test_from_bytes_with_encoding_malformed() {
+        let cases = vec![
+            (b"[1,".to_vec(), Encoding::Utf8),
+            (utf16le("{\"a\" 1}"), Encoding::Utf16Le),
+            (b"\xE9".to_vec(), Encoding::Latin1),
+        ];
+
+        for (input, encoding) in cases {
+            assert!(
+                from_bytes_with_encoding(&input, encoding).is_err(),
+                "{:?}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_encode_round_trip() {
+        let text = "{\"name\": \"caf\u{e9}\"}";
+        let cases = vec![
+            (Encoding::Utf8, true),
+            (Encoding::Utf8, false),
+            (Encoding::Utf16Le, true),
+            (Encoding::Utf16Be, true),
+            (Encoding::Utf16Be, false),
+            (Encoding::Latin1, false),
+        ];
+
+        for (encoding, bom) in cases {
+            let bytes = encode(text, encoding, bom).unwrap();
+            assert_eq!(detect_bom(&bytes).is_some(), bom, "{:?}", encoding);
+            assert_eq!(decode(&bytes, encoding).unwrap(), text, "{:?}", encoding);
+        }
+        assert!(encode("\u{20ac}", Encoding::Latin1, false).is_err());
+    }
+
+    #[test]
+    fn test_decode_malformed() {
+        assert!(decode(&[b'[', 0xFF, b']'], Encoding::Utf8).is_err());
+        assert!(decode(&[b'[', 0x00, b']'], Encoding::Utf16Le).is_err());
+    }
+}
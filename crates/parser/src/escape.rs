@@ -0,0 +1,257 @@
+/// Turns `s` into a JSON string literal, quotes included.
+pub fn escape_string(s: &str) -> String {
+    escape_string_with_quote(s, '"')
+}
+
+/// Like `escape_string`, but delimits the literal with `quote`, e.g. `'` for
+/// JSON5. Only that quote character is escaped.
+pub fn escape_string_with_quote(s: &str, quote: char) -> String {
+    escape(s, quote, false)
+}
+
+/// Like `escape_string_with_quote`, but keeps the literal ASCII by writing
+/// every other character as `\uXXXX`, with a surrogate pair for characters
+/// outside the Basic Multilingual Plane.
+pub fn escape_string_ascii(s: &str, quote: char) -> String {
+    escape(s, quote, true)
+}
+
+fn escape(s: &str, quote: char, ascii_only: bool) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push(quote);
+    for c in s.chars() {
+        match c {
+            c if c == quote => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            '\u{08}' => escaped.push_str("\\b"),
+            '\u{0C}' => escaped.push_str("\\f"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c if ascii_only && !c.is_ascii() => {
+                for unit in c.encode_utf16(&mut [0; 2]) {
+                    escaped.push_str(&format!("\\u{:04x}", unit));
+                }
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped.push(quote);
+    escaped
+}
+
+/// Decodes the escape sequences in the contents of a JSON string literal,
+/// i.e. the text between the quotes.
+pub fn unescape_string(literal: &str) -> Result<String, String> {
+    unescape_string_with_quote(literal, '"')
+}
+
+/// Like `unescape_string`, for a literal delimited by `quote`. A `'`-quoted
+/// literal additionally accepts `\'`. The quote itself and control
+/// characters below U+0020 must be escaped.
+pub fn unescape_string_with_quote(literal: &str, quote: char) -> Result<String, String> {
+    let mut unescaped = String::with_capacity(literal.len());
+    let mut chars = literal.chars();
+    while let Some(c) = chars.next() {
+        if c == quote {
+            return Err(format!("Unescaped quote: {}", c));
+        }
+        if (c as u32) < 0x20 {
+            return Err(format!("Unescaped control character: U+{:04X}", c as u32));
+        }
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('"') => unescaped.push('"'),
+            Some('\'') if quote == '\'' => unescaped.push('\''),
+            Some('\\') => unescaped.push('\\'),
+            Some('/') => unescaped.push('/'),
+            Some('b') => unescaped.push('\u{08}'),
+            Some('f') => unescaped.push('\u{0C}'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some('t') => unescaped.push('\t'),
+            Some('u') => {
+                let high = read_hex4(&mut chars)?;
+                let code_point = match high {
+                    0xD800..=0xDBFF => {
+                        if chars.next() != Some('\\') || chars.next() != Some('u') {
+                            return Err(format!("Unpaired surrogate: \\u{:04X}", high));
+                        }
+                        let low = read_hex4(&mut chars)?;
+                        if !(0xDC00..=0xDFFF).contains(&low) {
+                            return Err(format!("Unpaired surrogate: \\u{:04X}", high));
+                        }
+                        0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+                    }
+                    0xDC00..=0xDFFF => {
+                        return Err(format!("Unpaired surrogate: \\u{:04X}", high));
+                    }
+                    _ => high,
+                };
+                match char::from_u32(code_point) {
+                    Some(c) => unescaped.push(c),
+                    None => return Err(format!("Invalid code point: {:X}", code_point)),
+                }
+            }
+            Some(c) => return Err(format!("Invalid escape sequence: \\{}", c)),
+            None => return Err("Unexpected end of escape sequence".to_string()),
+        }
+    }
+    Ok(unescaped)
+}
+
+fn read_hex4(chars: &mut std::str::Chars) -> Result<u32, String> {
+    let mut value = 0;
+    for _ in 0..4 {
+        match chars.next().and_then(|c| c.to_digit(16)) {
+            Some(digit) => value = value * 16 + digit,
+            None => return Err("Invalid unicode escape, expected 4 hex digits".to_string()),
+        }
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_string() {
+        let cases = vec![
+            ("hello", r#""hello""#),
+            (r#"say "hi""#, r#""say \"hi\"""#),
+            (r"C:\path", r#""C:\\path""#),
+            ("line\nbreak\r\n", r#""line\nbreak\r\n""#),
+            ("tab\there", r#""tab\there""#),
+            ("\u{08}\u{0C}\u{01}\u{1F}", r#""\b\f\u0001\u001f""#),
+            ("caf\u{e9} \u{1F600}", "\"caf\u{e9} \u{1F600}\""),
+            ("", r#""""#),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(escape_string(input), expected);
+        }
+    }
+
+    #[test]
+    fn test_escape_string_with_quote() {
+        let cases = vec![
+            ("it's", '\'', r"'it\'s'"),
+            (r#"say "hi""#, '\'', r#"'say "hi"'"#),
+            ("a\\b\n", '\'', r"'a\\b\n'"),
+            ("it's", '"', r#""it's""#),
+        ];
+
+        for (input, quote, expected) in cases {
+            assert_eq!(escape_string_with_quote(input, quote), expected);
+        }
+    }
+
+    #[test]
+    fn test_escape_string_ascii() {
+        let cases = vec![
+            ("plain", '"', r#""plain""#),
+            ("caf\u{e9}", '"', r#""caf\u00e9""#),
+            ("\u{1F600}!", '"', r#""\ud83d\ude00!""#),
+            ("\u{4e2d}\n", '"', r#""\u4e2d\n""#),
+            ("it's \u{e9}", '\'', r"'it\'s \u00e9'"),
+        ];
+
+        for (input, quote, expected) in cases {
+            let escaped = escape_string_ascii(input, quote);
+            assert_eq!(escaped, expected);
+            assert_eq!(
+                unescape_string_with_quote(&escaped[1..escaped.len() - 1], quote),
+                Ok(input.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn test_unescape_string() {
+        let cases = vec![
+            (r#"hello"#, Ok("hello".to_string())),
+            (r#"say \"hi\""#, Ok(r#"say "hi""#.to_string())),
+            (
+                r#"\\ \/ \b \f \n \r \t"#,
+                Ok("\\ / \u{08} \u{0C} \n \r \t".to_string()),
+            ),
+            (r#"caf\u00e9"#, Ok("caf\u{e9}".to_string())),
+            (r#"\uD83D\uDE00"#, Ok("\u{1F600}".to_string())),
+            (r#"\x41"#, Err(r#"Invalid escape sequence: \x"#.to_string())),
+            (
+                r#"\u12"#,
+                Err("Invalid unicode escape, expected 4 hex digits".to_string()),
+            ),
+            (
+                r#"\uD83D"#,
+                Err(r#"Unpaired surrogate: \uD83D"#.to_string()),
+            ),
+            (
+                r#"\uDE00"#,
+                Err(r#"Unpaired surrogate: \uDE00"#.to_string()),
+            ),
+            (
+                r#"end\"#,
+                Err("Unexpected end of escape sequence".to_string()),
+            ),
+            (r#"say "hi""#, Err(r#"Unescaped quote: ""#.to_string())),
+            (
+                "line\nbreak",
+                Err("Unescaped control character: U+000A".to_string()),
+            ),
+            (
+                "tab\there",
+                Err("Unescaped control character: U+0009".to_string()),
+            ),
+            (
+                "\u{0}",
+                Err("Unescaped control character: U+0000".to_string()),
+            ),
+            (
+                "\u{1F}",
+                Err("Unescaped control character: U+001F".to_string()),
+            ),
+            ("\u{7F} ", Ok("\u{7F} ".to_string())),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(unescape_string(input), expected);
+        }
+    }
+
+    #[test]
+    fn test_unescape_string_with_quote() {
+        let cases = vec![
+            (r#"it's"#, '"', Ok("it's".to_string())),
+            (r#"it's"#, '\'', Err("Unescaped quote: '".to_string())),
+            (r#"say \"hi\""#, '\'', Ok(r#"say "hi""#.to_string())),
+            (
+                r#"it\'s"#,
+                '"',
+                Err(r#"Invalid escape sequence: \'"#.to_string()),
+            ),
+        ];
+
+        for (input, quote, expected) in cases {
+            assert_eq!(unescape_string_with_quote(input, quote), expected);
+        }
+    }
+
+    #[test]
+    fn test_escape_round_trip() {
+        let input = "quote \" slash \\ newline \n control \u{01} emoji \u{1F600}";
+        let escaped = escape_string(input);
+        assert_eq!(
+            unescape_string(&escaped[1..escaped.len() - 1]),
+            Ok(input.to_string())
+        );
+    }
+}
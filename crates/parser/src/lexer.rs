@@ -1,116 +1,494 @@
+use std::{fmt, iter::Peekable, ops::Range, str::CharIndices};
+
 use crate::{
+    escape::unescape_string_with_quote,
+    node::Number,
     token::{Token, CHAR_TOKENS, KEYWORD_TOKENS},
-    utility::PeekableIter,
 };
 
-pub struct Lexer {
-    char_stream: PeekableIter<char>,
+/// An error found while tokenizing. Every variant carries the byte offset in
+/// the input where the offending character or token starts.
+#[derive(Debug, PartialEq, Clone)]
+pub enum LexError {
+    UnexpectedCharacter {
+        character: char,
+        offset: usize,
+    },
+    UnexpectedEnd {
+        offset: usize,
+    },
+    InvalidNumber {
+        lexeme: String,
+        offset: usize,
+    },
+    InvalidKeyword {
+        keyword: String,
+        offset: usize,
+    },
+    InvalidString {
+        message: String,
+        offset: usize,
+    },
+    /// The input bytes are not valid UTF-8 from `offset` on.
+    InvalidUtf8 {
+        offset: usize,
+    },
+}
+
+impl LexError {
+    pub fn offset(&self) -> usize {
+        match self {
+            LexError::UnexpectedCharacter { offset, .. }
+            | LexError::UnexpectedEnd { offset }
+            | LexError::InvalidNumber { offset, .. }
+            | LexError::InvalidKeyword { offset, .. }
+            | LexError::InvalidString { offset, .. }
+            | LexError::InvalidUtf8 { offset } => *offset,
+        }
+    }
+
+    /// The same error with its offset moved `by` bytes on, for text that was
+    /// lexed as a slice of a larger input.
+    pub(crate) fn shifted(mut self, by: usize) -> LexError {
+        match &mut self {
+            LexError::UnexpectedCharacter { offset, .. }
+            | LexError::UnexpectedEnd { offset }
+            | LexError::InvalidNumber { offset, .. }
+            | LexError::InvalidKeyword { offset, .. }
+            | LexError::InvalidString { offset, .. }
+            | LexError::InvalidUtf8 { offset } => *offset += by,
+        }
+        self
+    }
 }
 
-impl Lexer {
-    pub fn new(input: &str) -> Self {
-        let vec: Vec<char> = input.chars().collect();
-        let char_stream = vec.into_iter().peekable();
-        Lexer { char_stream }
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LexError::UnexpectedCharacter { character, .. } => {
+                write!(f, "Unexpected character: {}", character)?
+            }
+            LexError::UnexpectedEnd { .. } => write!(f, "Unexpected end of input")?,
+            LexError::InvalidNumber { lexeme, .. } => write!(f, "Unexpected number: {}", lexeme)?,
+            LexError::InvalidKeyword { keyword, .. } => {
+                write!(f, "Unexpected keyword: {}", keyword)?
+            }
+            LexError::InvalidString { message, .. } => write!(f, "{}", message)?,
+            LexError::InvalidUtf8 { .. } => write!(f, "Invalid UTF-8")?,
+        }
+        write!(f, " at byte {}", self.offset())
     }
+}
 
-    fn consume_char(&mut self) -> Token {
-        match self.char_stream.next() {
+impl std::error::Error for LexError {}
+
+pub struct Lexer<'a> {
+    input: &'a str,
+    char_stream: Peekable<CharIndices<'a>>,
+    /// Byte offset in `input` that `char_stream` indices count from.
+    base: usize,
+    /// Line breaks consumed so far.
+    lines: usize,
+    /// Byte offset at which the last token starts.
+    token_start: usize,
+    /// Byte offset just past the last token.
+    token_end: usize,
+    /// `lines` as it was when the previous token ended.
+    token_end_lines: usize,
+    /// Whether a line break came between the last token and the one before.
+    after_line_break: bool,
+    allow_comments: bool,
+    /// Byte ranges of the comments skipped so far.
+    comments: Vec<Range<usize>>,
+    allow_identifiers: bool,
+    allow_single_quotes: bool,
+    allow_non_finite: bool,
+    /// Set once `Token::End` or an error has been yielded by the iterator.
+    finished: bool,
+    #[cfg(feature = "decimal")]
+    decimal: bool,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Lexer {
+            input,
+            char_stream: input.char_indices().peekable(),
+            base: 0,
+            lines: 0,
+            token_start: 0,
+            token_end: 0,
+            token_end_lines: 0,
+            after_line_break: false,
+            allow_comments: false,
+            comments: Vec::new(),
+            allow_identifiers: false,
+            allow_single_quotes: false,
+            allow_non_finite: false,
+            finished: false,
+            #[cfg(feature = "decimal")]
+            decimal: false,
+        }
+    }
+
+    /// Skips `//` line comments and `/* */` block comments as whitespace.
+    pub fn allow_comments(mut self, allow: bool) -> Self {
+        self.allow_comments = allow;
+        self
+    }
+
+    /// Reads bare words that are not keywords as `Token::Identifier` instead
+    /// of failing.
+    pub fn allow_identifiers(mut self, allow: bool) -> Self {
+        self.allow_identifiers = allow;
+        self
+    }
+
+    /// Reads `'`-delimited strings as well as `"`-delimited ones.
+    pub fn allow_single_quotes(mut self, allow: bool) -> Self {
+        self.allow_single_quotes = allow;
+        self
+    }
+
+    /// Lexes raw bytes, failing with the offset of the first byte that does
+    /// not start a valid UTF-8 sequence.
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<Self, LexError> {
+        match std::str::from_utf8(bytes) {
+            Ok(input) => Ok(Lexer::new(input)),
+            Err(e) => Err(LexError::InvalidUtf8 {
+                offset: e.valid_up_to(),
+            }),
+        }
+    }
+
+    /// Reads `Infinity`, `-Infinity` and `NaN` as numbers.
+    pub fn allow_non_finite(mut self, allow: bool) -> Self {
+        self.allow_non_finite = allow;
+        self
+    }
+
+    /// Like `new`, but numbers become `Token::DecimalValue` when `decimal` is
+    /// set, keeping the exact digits of the input.
+    #[cfg(feature = "decimal")]
+    pub fn with_decimal(input: &'a str, decimal: bool) -> Self {
+        Lexer {
+            decimal,
+            ..Lexer::new(input)
+        }
+    }
+
+    /// Byte offset of the next character, or the input length at the end.
+    fn offset(&mut self) -> usize {
+        match self.char_stream.peek() {
+            Some(&(index, _)) => self.base + index,
+            None => self.input.len(),
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.char_stream.peek().map(|&(_, c)| c)
+    }
+
+    /// Advances past the next character, counting line breaks.
+    fn bump(&mut self) -> Option<char> {
+        let (_, c) = self.char_stream.next()?;
+        if c == '\n' {
+            self.lines += 1;
+        }
+        Some(c)
+    }
+
+    fn consume_char(&mut self) -> Result<Token, LexError> {
+        let offset = self.offset();
+        match self.bump() {
             Some(c) => match CHAR_TOKENS.get(&c) {
-                Some(token) => token.clone(),
-                None => panic!("Unexpected character: {}", c),
+                Some(token) => Ok(token.clone()),
+                None => Err(LexError::UnexpectedCharacter {
+                    character: c,
+                    offset,
+                }),
             },
-            None => panic!("Unexpected char of input"),
+            None => Err(LexError::UnexpectedEnd { offset }),
         }
     }
 
-    fn consume_string(&mut self) -> Token {
-        if self.char_stream.peek() == Some(&'"') {
-            self.char_stream.next(); // the first "
-        }
+    fn consume_string(&mut self) -> Result<Token, LexError> {
+        let offset = self.offset();
+        let quote = match self.peek() {
+            Some(quote @ ('"' | '\'')) => {
+                self.bump(); // the opening quote
+                quote
+            }
+            _ => '"',
+        };
         let mut s = String::new();
+        let mut escaped = false;
         loop {
-            match self.char_stream.next() {
-                Some('"') => break,
-                Some(c) => s.push(c),
-                None => panic!("Unexpected end of input"),
+            match self.bump() {
+                Some(c) if c == quote && !escaped => break,
+                Some(c) => {
+                    escaped = !escaped && c == '\\';
+                    s.push(c);
+                }
+                None => {
+                    return Err(LexError::UnexpectedEnd {
+                        offset: self.offset(),
+                    })
+                }
             }
         }
-        Token::StringValue(s)
+        match unescape_string_with_quote(&s, quote) {
+            Ok(s) => Ok(Token::StringValue(s)),
+            Err(message) => Err(LexError::InvalidString { message, offset }),
+        }
     }
 
-    fn consume_number(&mut self) -> Token {
+    fn consume_number(&mut self) -> Result<Token, LexError> {
+        let offset = self.offset();
         let mut s = String::new();
-        loop {
-            match self.char_stream.peek() {
-                Some(c) if c.is_numeric() || c == &'.' => match self.char_stream.next() {
-                    Some(c) => s.push(c),
-                    None => panic!("Unexpected end of input"),
-                },
-                _ => break,
+        while let Some(c) = self.peek() {
+            if !(c.is_ascii_digit() || matches!(c, '.' | '-' | '+' | 'e' | 'E')) {
+                break;
+            }
+            s.push(c);
+            self.bump();
+        }
+        if self.allow_non_finite && s == "-" && self.peek() == Some('I') {
+            while let Some(c) = self.peek() {
+                if !c.is_alphanumeric() {
+                    break;
+                }
+                s.push(c);
+                self.bump();
+            }
+            if s == "-Infinity" {
+                return Ok(Token::NumberValue(f64::NEG_INFINITY.into()));
             }
         }
+        let invalid = |lexeme: String| LexError::InvalidNumber { lexeme, offset };
+        if !is_json_number(&s) {
+            return Err(invalid(s));
+        }
+        #[cfg(feature = "decimal")]
+        if self.decimal {
+            use std::str::FromStr;
+            let decimal = rust_decimal::Decimal::from_str(&s)
+                .or_else(|_| rust_decimal::Decimal::from_scientific(&s));
+            return match decimal {
+                Ok(d) => Ok(Token::DecimalValue(d)),
+                Err(_) => Err(invalid(s)),
+            };
+        }
         match s.parse::<f64>() {
-            Ok(n) => Token::NumberValue(n),
-            Err(_) => panic!("Unexpected number: {}", s),
+            Ok(value) => Ok(Token::NumberValue(Number { value, lexeme: s })),
+            Err(_) => Err(invalid(s)),
         }
     }
 
-    fn consume_keyword(&mut self) -> Token {
+    fn consume_keyword(&mut self) -> Result<Token, LexError> {
+        let offset = self.offset();
         let mut keyword = String::new();
-        loop {
-            let c = self.char_stream.peek();
-            match c {
-                Some(c) if c.is_alphanumeric() => {
-                    keyword.push(*c);
-                    self.char_stream.next();
-                }
-                _ => break,
+        while let Some(c) = self.peek() {
+            if !(c.is_alphanumeric() || (self.allow_identifiers && matches!(c, '_' | '$'))) {
+                break;
             }
+            keyword.push(c);
+            self.bump();
         }
         match KEYWORD_TOKENS.get(&keyword[..]) {
-            Some(token) => token.clone(),
-            None => panic!("Unexpected keyword: {}", keyword),
+            Some(token) => Ok(token.clone()),
+            None if self.allow_non_finite && keyword == "Infinity" => {
+                Ok(Token::NumberValue(f64::INFINITY.into()))
+            }
+            None if self.allow_non_finite && keyword == "NaN" => {
+                Ok(Token::NumberValue(f64::NAN.into()))
+            }
+            None if self.allow_identifiers => Ok(Token::Identifier(keyword)),
+            None => Err(LexError::InvalidKeyword { keyword, offset }),
         }
     }
 
+    /// Skips a run of whitespace in one go: ASCII whitespace is scanned as
+    /// bytes and anything else `char::is_whitespace` accepts falls back to
+    /// `trim_start`, then the char iterator restarts past the run.
     fn consume_whitespace(&mut self) {
-        loop {
-            match self.char_stream.peek() {
-                Some(c) if c.is_whitespace() => {
-                    self.char_stream.next();
+        let start = self.offset();
+        let rest = &self.input[start..];
+        let bytes = rest.as_bytes();
+        let mut len = bytes
+            .iter()
+            .position(|b| !matches!(b, b' ' | b'\t' | b'\n' | b'\r'))
+            .unwrap_or(bytes.len());
+        let tail = &rest[len..];
+        if tail.starts_with(|c: char| !c.is_ascii() || matches!(c, '\u{b}' | '\u{c}')) {
+            len += tail.len() - tail.trim_start().len();
+        }
+        if len == 0 {
+            return;
+        }
+        self.lines += bytes[..len].iter().filter(|&&b| b == b'\n').count();
+        self.base = start + len;
+        self.char_stream = self.input[self.base..].char_indices().peekable();
+    }
+
+    fn consume_comment(&mut self) -> Result<(), LexError> {
+        let offset = self.offset();
+        self.bump(); // the first /
+        match self.bump() {
+            Some('/') => {
+                while let Some(c) = self.bump() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            Some('*') => loop {
+                match self.bump() {
+                    Some('*') if self.peek() == Some('/') => {
+                        self.bump();
+                        break;
+                    }
+                    Some(_) => {}
+                    None => {
+                        return Err(LexError::UnexpectedEnd {
+                            offset: self.offset(),
+                        })
+                    }
                 }
-                _ => break,
+            },
+            _ => {
+                return Err(LexError::UnexpectedCharacter {
+                    character: '/',
+                    offset,
+                })
             }
         }
+        // a line comment ends before its line break
+        let end = offset + self.input[offset..self.offset()].trim_end().len();
+        self.comments.push(offset..end);
+        Ok(())
     }
 
-    fn next_token(&mut self) -> Token {
+    pub fn next_token(&mut self) -> Result<Token, LexError> {
         self.consume_whitespace();
-        let c = self.char_stream.peek();
-        match c {
+        while self.allow_comments && self.peek() == Some('/') {
+            self.consume_comment()?;
+            self.consume_whitespace();
+        }
+        self.after_line_break = self.lines > self.token_end_lines;
+        self.token_start = self.offset();
+        let token = match self.peek() {
             Some(c) => match c {
                 '{' | '}' | '[' | ']' | ':' | ',' => self.consume_char(),
                 '"' => self.consume_string(),
-                '0'..='9' => self.consume_number(),
+                '\'' if self.allow_single_quotes => self.consume_string(),
+                '-' | '0'..='9' => self.consume_number(),
                 'a'..='z' | 'A'..='Z' => self.consume_keyword(),
-                _ => panic!("Unexpected character: {}", c),
+                '_' | '$' if self.allow_identifiers => self.consume_keyword(),
+                _ => Err(LexError::UnexpectedCharacter {
+                    character: c,
+                    offset: self.offset(),
+                }),
             },
-            None => Token::End,
-        }
+            None => Ok(Token::End),
+        };
+        self.token_end = self.offset();
+        self.token_end_lines = self.lines;
+        token
     }
 
-    pub fn tokenize(&mut self) -> Vec<Token> {
-        let mut tokens = Vec::new();
-        loop {
-            let token = self.next_token();
-            tokens.push(token.clone());
-            if token == Token::End {
+    pub(crate) fn after_line_break(&self) -> bool {
+        self.after_line_break
+    }
+
+    pub(crate) fn token_start(&self) -> usize {
+        self.token_start
+    }
+
+    pub(crate) fn token_end(&self) -> usize {
+        self.token_end
+    }
+
+    /// Byte ranges of the comments skipped so far, delimiters included.
+    pub fn comments(&self) -> &[Range<usize>] {
+        &self.comments
+    }
+
+    /// Discards the rest of the current line, including an error at its
+    /// start, so the iterator resumes with the next line.
+    pub fn skip_line(&mut self) {
+        while let Some(c) = self.bump() {
+            if c == '\n' {
                 break;
             }
         }
-        tokens
+        self.finished = false;
+    }
+
+    pub fn tokenize(&mut self) -> Result<Vec<Token>, LexError> {
+        self.collect()
+    }
+
+    /// Like `tokenize`, but panics on the first error.
+    pub fn tokenize_unwrap(&mut self) -> Vec<Token> {
+        match self.tokenize() {
+            Ok(tokens) => tokens,
+            Err(e) => panic!("{}", e),
+        }
+    }
+}
+
+/// Yields tokens lazily, ending after `Token::End` or the first error.
+impl Iterator for Lexer<'_> {
+    type Item = Result<Token, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let token = self.next_token();
+        self.finished = matches!(token, Ok(Token::End) | Err(_));
+        Some(token)
+    }
+}
+
+// -? ( 0 | [1-9] digits? ) ( . digits )? ( [eE] [+-]? digits )?
+fn is_json_number(s: &str) -> bool {
+    fn digits(bytes: &[u8], mut i: usize) -> Option<usize> {
+        let start = i;
+        while i < bytes.len() && bytes[i].is_ascii_digit() {
+            i += 1;
+        }
+        (i > start).then_some(i)
+    }
+
+    let bytes = s.as_bytes();
+    let mut i = if bytes.first() == Some(&b'-') { 1 } else { 0 };
+    i = match bytes.get(i) {
+        // a leading zero stands alone
+        Some(b'0') => i + 1,
+        _ => match digits(bytes, i) {
+            Some(i) => i,
+            None => return false,
+        },
+    };
+    if bytes.get(i) == Some(&b'.') {
+        i = match digits(bytes, i + 1) {
+            Some(i) => i,
+            None => return false,
+        };
+    }
+    if matches!(bytes.get(i), Some(b'e' | b'E')) {
+        i += 1;
+        if matches!(bytes.get(i), Some(b'+' | b'-')) {
+            i += 1;
+        }
+        i = match digits(bytes, i) {
+            Some(i) => i,
+            None => return false,
+        };
     }
+    i == bytes.len()
 }
 
 #[cfg(test)]
@@ -121,71 +499,571 @@ mod tests {
     fn test_consume_char() {
         let input = r#"{}[]:,"#;
         let mut lexer = Lexer::new(input);
-        assert_eq!(lexer.consume_char(), Token::LBrace); // {
-        assert_eq!(lexer.consume_char(), Token::RBrace); // }
-        assert_eq!(lexer.consume_char(), Token::LBracket); // [
-        assert_eq!(lexer.consume_char(), Token::RBracket); // ]
-        assert_eq!(lexer.consume_char(), Token::Colon); // :
-        assert_eq!(lexer.consume_char(), Token::Comma); // ,
+        assert_eq!(lexer.consume_char().unwrap(), Token::LBrace); // {
+        assert_eq!(lexer.consume_char().unwrap(), Token::RBrace); // }
+        assert_eq!(lexer.consume_char().unwrap(), Token::LBracket); // [
+        assert_eq!(lexer.consume_char().unwrap(), Token::RBracket); // ]
+        assert_eq!(lexer.consume_char().unwrap(), Token::Colon); // :
+        assert_eq!(lexer.consume_char().unwrap(), Token::Comma); // ,
     }
 
     #[test]
     fn test_consume_string() {
         let input = r#"{"foo":"bar"}"#;
         let mut lexer = Lexer::new(input);
-        assert_eq!(lexer.consume_char(), Token::LBrace); // {
+        assert_eq!(lexer.consume_char().unwrap(), Token::LBrace); // {
         assert_eq!(
-            lexer.consume_string(),
+            lexer.consume_string().unwrap(),
             Token::StringValue("foo".to_string())
         ); // "foo"
-        assert_eq!(lexer.consume_char(), Token::Colon); // :
+        assert_eq!(lexer.consume_char().unwrap(), Token::Colon); // :
         assert_eq!(
-            lexer.consume_string(),
+            lexer.consume_string().unwrap(),
             Token::StringValue("bar".to_string())
         ); // "bar"
-        assert_eq!(lexer.consume_char(), Token::RBrace); // }
+        assert_eq!(lexer.consume_char().unwrap(), Token::RBrace); // }
+    }
+
+    #[test]
+    fn test_consume_string_escapes() {
+        let cases = vec![
+            (r#""a\"b""#, "a\"b"),
+            (r#""a\\b""#, "a\\b"),
+            (r#""a\/b""#, "a/b"),
+            (r#""a\bb""#, "a\u{08}b"),
+            (r#""a\fb""#, "a\u{0C}b"),
+            (r#""a\nb""#, "a\nb"),
+            (r#""a\rb""#, "a\rb"),
+            (r#""a\tb""#, "a\tb"),
+            (r#""\u00e9""#, "\u{e9}"),
+            (r#""\uD83D\uDE00""#, "\u{1F600}"),
+            (r#""ends with \\""#, "ends with \\"),
+        ];
+
+        for (input, expected) in cases {
+            let mut lexer = Lexer::new(input);
+            assert_eq!(
+                lexer.consume_string().unwrap(),
+                Token::StringValue(expected.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn test_consume_string_invalid_escape() {
+        let mut lexer = Lexer::new(r#""\x41""#);
+        assert_eq!(
+            lexer.consume_string(),
+            Err(LexError::InvalidString {
+                message: "Invalid escape sequence: \\x".to_string(),
+                offset: 0,
+            })
+        );
+    }
+
+    #[test]
+    fn test_comments() {
+        let cases = vec![
+            (
+                "[1] // trailing",
+                vec![
+                    Token::LBracket,
+                    Token::NumberValue(1.0.into()),
+                    Token::RBracket,
+                ],
+            ),
+            (
+                "[1, /* two */ 3]",
+                vec![
+                    Token::LBracket,
+                    Token::NumberValue(1.0.into()),
+                    Token::Comma,
+                    Token::NumberValue(3.0.into()),
+                    Token::RBracket,
+                ],
+            ),
+            (
+                "// leading\n/**/\"a // b /* c */\"",
+                vec![Token::StringValue("a // b /* c */".to_string())],
+            ),
+        ];
+
+        for (input, mut expected) in cases {
+            expected.push(Token::End);
+            let mut lexer = Lexer::new(input).allow_comments(true);
+            assert_eq!(lexer.tokenize().unwrap(), expected);
+        }
+
+        let input = "[1, // one\r\n/* two\n */ 2] //";
+        let mut lexer = Lexer::new(input).allow_comments(true);
+        lexer.tokenize().unwrap();
+        let comments: Vec<&str> = lexer
+            .comments()
+            .iter()
+            .map(|range| &input[range.clone()])
+            .collect();
+        assert_eq!(comments, vec!["// one", "/* two\n */", "//"]);
+    }
+
+    #[test]
+    fn test_comment_errors() {
+        let cases = vec![
+            (
+                "[1] // trailing",
+                false,
+                LexError::UnexpectedCharacter {
+                    character: '/',
+                    offset: 4,
+                },
+            ),
+            (
+                "[1] / 2",
+                true,
+                LexError::UnexpectedCharacter {
+                    character: '/',
+                    offset: 4,
+                },
+            ),
+            ("[1] /* open", true, LexError::UnexpectedEnd { offset: 11 }),
+        ];
+
+        for (input, allow, expected) in cases {
+            let mut lexer = Lexer::new(input).allow_comments(allow);
+            assert_eq!(lexer.tokenize(), Err(expected));
+        }
     }
 
     #[test]
     fn test_consume_number() {
         let input = r#"{"foo":123}"#;
         let mut lexer = Lexer::new(input);
-        assert_eq!(lexer.consume_char(), Token::LBrace); // {
+        assert_eq!(lexer.consume_char().unwrap(), Token::LBrace); // {
         assert_eq!(
-            lexer.consume_string(),
+            lexer.consume_string().unwrap(),
             Token::StringValue("foo".to_string())
         ); // "foo"
-        assert_eq!(lexer.consume_char(), Token::Colon); // :
-        assert_eq!(lexer.consume_number(), Token::NumberValue(123.0)); // 123
-        assert_eq!(lexer.consume_char(), Token::RBrace); // }
+        assert_eq!(lexer.consume_char().unwrap(), Token::Colon); // :
+        assert_eq!(
+            lexer.consume_number().unwrap(),
+            Token::NumberValue(123.0.into())
+        ); // 123
+        assert_eq!(lexer.consume_char().unwrap(), Token::RBrace); // }
+    }
+
+    #[test]
+    fn test_consume_signed_and_exponent_number() {
+        let cases = vec![
+            ("-0", -0.0),
+            ("1e3", 1000.0),
+            ("-1.5e-2", -0.015),
+            ("2.5E+2", 250.0),
+            ("-12.75", -12.75),
+            ("1.0", 1.0),
+            ("10000000000000001", 1e16),
+        ];
+
+        for (input, expected) in cases {
+            let mut lexer = Lexer::new(input);
+            let expected = Number {
+                value: expected,
+                lexeme: input.to_string(),
+            };
+            assert_eq!(
+                lexer.consume_number().unwrap(),
+                Token::NumberValue(expected)
+            );
+        }
+    }
+
+    #[test]
+    fn test_is_json_number() {
+        let cases = vec![
+            ("0", true),
+            ("-0", true),
+            ("1.5e10", true),
+            ("1E-3", true),
+            ("1..2", false),
+            ("1.2.3", false),
+            ("-", false),
+            ("1.", false),
+            (".5", false),
+            ("1e", false),
+            ("1e+", false),
+            ("1-2", false),
+            ("--1", false),
+            ("0.5", true),
+            ("10", true),
+            ("0e5", true),
+            ("01", false),
+            ("-007", false),
+            ("00.5", false),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(is_json_number(input), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_tokenize_strict_numbers() {
+        let cases = vec![
+            ("0", Ok(0.0)),
+            ("0.5", Ok(0.5)),
+            ("-0.5", Ok(-0.5)),
+            (
+                "01",
+                Err(LexError::InvalidNumber {
+                    lexeme: "01".to_string(),
+                    offset: 0,
+                }),
+            ),
+            (
+                "1.",
+                Err(LexError::InvalidNumber {
+                    lexeme: "1.".to_string(),
+                    offset: 0,
+                }),
+            ),
+            (
+                ".5",
+                Err(LexError::UnexpectedCharacter {
+                    character: '.',
+                    offset: 0,
+                }),
+            ),
+        ];
+
+        for (input, expected) in cases {
+            let mut lexer = Lexer::new(input);
+            let expected = expected.map(|value| {
+                let number = Number {
+                    value,
+                    lexeme: input.to_string(),
+                };
+                vec![Token::NumberValue(number), Token::End]
+            });
+            assert_eq!(lexer.tokenize(), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_iterator() {
+        let input = r#"{"a": [1, true, null]}"#;
+        let tokens: Vec<_> = Lexer::new(input).collect();
+        let expected: Vec<_> = Lexer::new(input)
+            .tokenize()
+            .unwrap()
+            .into_iter()
+            .map(Ok)
+            .collect();
+        assert_eq!(tokens, expected);
+        assert_eq!(tokens.last(), Some(&Ok(Token::End)));
+
+        let mut lexer = Lexer::new("[1, @, 2]");
+        assert_eq!(lexer.next(), Some(Ok(Token::LBracket)));
+        assert_eq!(lexer.next(), Some(Ok(Token::NumberValue(1.0.into()))));
+        assert_eq!(lexer.next(), Some(Ok(Token::Comma)));
+        assert!(matches!(lexer.next(), Some(Err(_))));
+        assert_eq!(lexer.next(), None);
+    }
+
+    #[test]
+    fn test_tokenize_large_input() {
+        let element =
+            r#"{"name": "日本語 ✓", "values": [1, -2.5e3, true, null], "nested": {"k": "v\n"}}"#;
+        let count = 10_000;
+        let input = format!("[{}]", vec![element; count].join(",\n"));
+
+        let mut element_tokens = Lexer::new(element).tokenize_unwrap();
+        element_tokens.pop(); // End
+        let mut expected = vec![Token::LBracket];
+        for i in 0..count {
+            if i > 0 {
+                expected.push(Token::Comma);
+            }
+            expected.extend(element_tokens.iter().cloned());
+        }
+        expected.extend([Token::RBracket, Token::End]);
+        assert_eq!(Lexer::new(&input).tokenize_unwrap(), expected);
+
+        // byte offsets stay right past the multi-byte characters
+        let mut lexer = Lexer::new(&input);
+        let mut starts = Vec::new();
+        let mut depth = 0;
+        loop {
+            match lexer.next_token().unwrap() {
+                Token::End => break,
+                Token::LBrace => {
+                    if depth == 0 {
+                        starts.push(lexer.token_start());
+                    }
+                    depth += 1;
+                }
+                Token::RBrace => depth -= 1,
+                _ => {}
+            }
+        }
+        let stride = element.len() + ",\n".len();
+        assert_eq!(starts.len(), count);
+        assert_eq!(starts[..3], [1, 1 + stride, 1 + 2 * stride]);
+        assert_eq!(starts[count - 1], 1 + (count - 1) * stride);
+    }
+
+    #[test]
+    fn test_from_bytes() {
+        let tokens =
+            Lexer::from_bytes("[\"caf\u{e9}\"]".as_bytes()).and_then(|mut lexer| lexer.tokenize());
+        assert_eq!(
+            tokens,
+            Ok(vec![
+                Token::LBracket,
+                Token::StringValue("caf\u{e9}".to_string()),
+                Token::RBracket,
+                Token::End,
+            ])
+        );
+
+        let cases = vec![
+            (&b"[1, \xFF]"[..], 4),
+            (&b"\"caf\xC3\""[..], 4),
+            (&b"\xE2\x82"[..], 0),
+        ];
+
+        for (input, offset) in cases {
+            assert_eq!(
+                Lexer::from_bytes(input).err(),
+                Some(LexError::InvalidUtf8 { offset })
+            );
+        }
+    }
+
+    #[test]
+    fn test_skip_line() {
+        let mut lexer = Lexer::new("[1, @, 2]\n  true @\nnull");
+        assert_eq!(lexer.next(), Some(Ok(Token::LBracket)));
+        lexer.skip_line();
+        assert_eq!(lexer.next(), Some(Ok(Token::BooleanValue(true))));
+        assert!(lexer.after_line_break());
+        assert!(matches!(lexer.next(), Some(Err(_))));
+        lexer.skip_line();
+        assert_eq!(lexer.next(), Some(Ok(Token::NullValue)));
+        assert!(lexer.after_line_break());
+        assert_eq!(lexer.next(), Some(Ok(Token::End)));
+        assert!(!lexer.after_line_break());
+        lexer.skip_line();
+        assert_eq!(lexer.next(), Some(Ok(Token::End)));
+    }
+
+    #[test]
+    fn test_tokenize_end() {
+        let cases = vec![
+            ("", vec![Token::End]),
+            ("  \n", vec![Token::End]),
+            ("[] ", vec![Token::LBracket, Token::RBracket, Token::End]),
+        ];
+
+        for (input, expected) in cases {
+            let mut lexer = Lexer::new(input);
+            assert_eq!(lexer.tokenize(), Ok(expected));
+        }
+    }
+
+    #[test]
+    fn test_tokenize_errors() {
+        let cases = vec![
+            (
+                "[1..2]",
+                LexError::InvalidNumber {
+                    lexeme: "1..2".to_string(),
+                    offset: 1,
+                },
+            ),
+            (
+                r#"{"a": tru}"#,
+                LexError::InvalidKeyword {
+                    keyword: "tru".to_string(),
+                    offset: 6,
+                },
+            ),
+            (
+                r#"{"é": @}"#,
+                LexError::UnexpectedCharacter {
+                    character: '@',
+                    offset: 7,
+                },
+            ),
+            (r#"["abc"#, LexError::UnexpectedEnd { offset: 5 }),
+            (
+                r#"["\u12"]"#,
+                LexError::InvalidString {
+                    message: "Invalid unicode escape, expected 4 hex digits".to_string(),
+                    offset: 1,
+                },
+            ),
+        ];
+
+        for (input, expected) in cases {
+            let mut lexer = Lexer::new(input);
+            assert_eq!(lexer.tokenize(), Err(expected));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Unexpected number: 1..2 at byte 1")]
+    fn test_tokenize_unwrap() {
+        let mut lexer = Lexer::new("[1..2]");
+        lexer.tokenize_unwrap();
+    }
+
+    #[cfg(feature = "decimal")]
+    #[test]
+    fn test_consume_decimal_number() {
+        use std::str::FromStr;
+
+        let cases = vec![
+            ("0.1", "0.1"),
+            ("-19.99", "-19.99"),
+            ("12345678901234567.89", "12345678901234567.89"),
+            ("1.5e2", "150"),
+        ];
+
+        for (input, expected) in cases {
+            let mut lexer = Lexer::with_decimal(input, true);
+            let expected = rust_decimal::Decimal::from_str(expected).unwrap();
+            assert_eq!(
+                lexer.consume_number().unwrap(),
+                Token::DecimalValue(expected)
+            );
+        }
     }
 
     #[test]
     fn test_consume_keyword() {
         let input = r#"{"foo":true,"bar":false,"baz":null}"#;
         let mut lexer = Lexer::new(input);
-        assert_eq!(lexer.consume_char(), Token::LBrace); // {
+        assert_eq!(lexer.consume_char().unwrap(), Token::LBrace); // {
         assert_eq!(
-            lexer.consume_string(),
+            lexer.consume_string().unwrap(),
             Token::StringValue("foo".to_string())
         ); // "foo"
-        assert_eq!(lexer.consume_char(), Token::Colon); // :
-        assert_eq!(lexer.consume_keyword(), Token::BooleanValue(true)); // true
-        assert_eq!(lexer.consume_char(), Token::Comma); // ,
+        assert_eq!(lexer.consume_char().unwrap(), Token::Colon); // :
+        assert_eq!(lexer.consume_keyword().unwrap(), Token::BooleanValue(true)); // true
+        assert_eq!(lexer.consume_char().unwrap(), Token::Comma); // ,
         assert_eq!(
-            lexer.consume_string(),
+            lexer.consume_string().unwrap(),
             Token::StringValue("bar".to_string())
         ); // "bar"
-        assert_eq!(lexer.consume_char(), Token::Colon); // :
-        assert_eq!(lexer.consume_keyword(), Token::BooleanValue(false)); // false
-        assert_eq!(lexer.consume_char(), Token::Comma); // ,
+        assert_eq!(lexer.consume_char().unwrap(), Token::Colon); // :
+        assert_eq!(lexer.consume_keyword().unwrap(), Token::BooleanValue(false)); // false
+        assert_eq!(lexer.consume_char().unwrap(), Token::Comma); // ,
         assert_eq!(
-            lexer.consume_string(),
+            lexer.consume_string().unwrap(),
             Token::StringValue("baz".to_string())
         ); // "baz"
-        assert_eq!(lexer.consume_char(), Token::Colon); // :
-        assert_eq!(lexer.consume_keyword(), Token::NullValue); // null
-        assert_eq!(lexer.consume_char(), Token::RBrace); // }
+        assert_eq!(lexer.consume_char().unwrap(), Token::Colon); // :
+        assert_eq!(lexer.consume_keyword().unwrap(), Token::NullValue); // null
+        assert_eq!(lexer.consume_char().unwrap(), Token::RBrace); // }
+    }
+
+    #[test]
+    fn test_single_quotes() {
+        let cases = vec![
+            (r#"'a'"#, Ok(Token::StringValue("a".to_string()))),
+            (
+                r#"'say "hi"'"#,
+                Ok(Token::StringValue(r#"say "hi""#.to_string())),
+            ),
+            (r#"'it\'s'"#, Ok(Token::StringValue("it's".to_string()))),
+            (r#""it's""#, Ok(Token::StringValue("it's".to_string()))),
+            (r#"'a""#, Err(LexError::UnexpectedEnd { offset: 3 })),
+        ];
+
+        for (input, expected) in cases {
+            let mut lexer = Lexer::new(input).allow_single_quotes(true);
+            assert_eq!(lexer.next_token(), expected);
+        }
+
+        assert_eq!(
+            Lexer::new("'a'").next_token(),
+            Err(LexError::UnexpectedCharacter {
+                character: '\'',
+                offset: 0,
+            })
+        );
+    }
+
+    #[test]
+    fn test_non_finite() {
+        let cases = vec![
+            ("Infinity", f64::INFINITY),
+            ("-Infinity", f64::NEG_INFINITY),
+            ("NaN", f64::NAN),
+        ];
+
+        for (input, expected) in cases {
+            let mut lexer = Lexer::new(input).allow_non_finite(true);
+            match lexer.next_token() {
+                Ok(Token::NumberValue(number)) => {
+                    assert_eq!(number.lexeme, input);
+                    assert_eq!(number.value.to_bits(), expected.to_bits());
+                }
+                token => panic!("{}: {:?}", input, token),
+            }
+        }
+
+        let cases = vec![
+            (
+                "Infinity",
+                false,
+                LexError::InvalidKeyword {
+                    keyword: "Infinity".to_string(),
+                    offset: 0,
+                },
+            ),
+            (
+                "-Infinity",
+                false,
+                LexError::InvalidNumber {
+                    lexeme: "-".to_string(),
+                    offset: 0,
+                },
+            ),
+            (
+                "-Inf",
+                true,
+                LexError::InvalidNumber {
+                    lexeme: "-Inf".to_string(),
+                    offset: 0,
+                },
+            ),
+        ];
+        for (input, allow, expected) in cases {
+            let mut lexer = Lexer::new(input).allow_non_finite(allow);
+            assert_eq!(lexer.next_token(), Err(expected));
+        }
+    }
+
+    #[test]
+    fn test_identifiers() {
+        let cases = vec![
+            ("foo", Ok(Token::Identifier("foo".to_string()))),
+            ("_a$1", Ok(Token::Identifier("_a$1".to_string()))),
+            ("$", Ok(Token::Identifier("$".to_string()))),
+            ("true", Ok(Token::BooleanValue(true))),
+        ];
+
+        for (input, expected) in cases {
+            let mut lexer = Lexer::new(input).allow_identifiers(true);
+            assert_eq!(lexer.next_token(), expected);
+        }
+
+        assert_eq!(
+            Lexer::new("foo").next_token(),
+            Err(LexError::InvalidKeyword {
+                keyword: "foo".to_string(),
+                offset: 0,
+            })
+        );
     }
 
     #[test]
@@ -193,29 +1071,65 @@ mod tests {
         let input = r#"{    "foo": 123
         }"#;
         let mut lexer = Lexer::new(input);
-        assert_eq!(lexer.consume_char(), Token::LBrace); // {
+        assert_eq!(lexer.consume_char().unwrap(), Token::LBrace); // {
         lexer.consume_whitespace(); // tab whitespace
         assert_eq!(
-            lexer.consume_string(),
+            lexer.consume_string().unwrap(),
             Token::StringValue("foo".to_string())
         ); // "foo"
-        assert_eq!(lexer.consume_char(), Token::Colon); // :
+        assert_eq!(lexer.consume_char().unwrap(), Token::Colon); // :
         lexer.consume_whitespace(); // space whitespace
-        assert_eq!(lexer.consume_number(), Token::NumberValue(123.0)); // 123
+        assert_eq!(
+            lexer.consume_number().unwrap(),
+            Token::NumberValue(123.0.into())
+        ); // 123
         lexer.consume_whitespace(); // new line whitespace
-        assert_eq!(lexer.consume_char(), Token::RBrace); // }
+        assert_eq!(lexer.consume_char().unwrap(), Token::RBrace); // }
+    }
+
+    #[test]
+    fn test_consume_whitespace_runs() {
+        // (input, offset after the run, line breaks in it)
+        let cases = vec![
+            ("1", 0, 0),
+            ("  \t1", 3, 0),
+            ("\r\n  \n1", 5, 2),
+            ("\u{b}\u{c} 1", 3, 0),
+            (" \u{a0}\u{3000}\n1", 7, 1),
+            (" \u{2028}é", 4, 0),
+            ("\n \n ", 4, 2),
+        ];
+
+        for (input, offset, lines) in cases {
+            let mut lexer = Lexer::new(input);
+            lexer.consume_whitespace();
+            assert_eq!(
+                (lexer.offset(), lexer.lines),
+                (offset, lines),
+                "{:?}",
+                input
+            );
+            let rest: String = std::iter::from_fn(|| lexer.bump()).collect();
+            assert_eq!(rest, &input[offset..], "{:?}", input);
+        }
     }
 
     #[test]
     fn test_next_token() {
         let input = r#"{"foo":123}"#;
         let mut lexer = Lexer::new(input);
-        assert_eq!(lexer.next_token(), Token::LBrace); // {
-        assert_eq!(lexer.next_token(), Token::StringValue("foo".to_string())); // "foo"
-        assert_eq!(lexer.next_token(), Token::Colon); // :
-        assert_eq!(lexer.next_token(), Token::NumberValue(123.0)); // 123
-        assert_eq!(lexer.next_token(), Token::RBrace); // }
-        assert_eq!(lexer.next_token(), Token::End); // end
+        assert_eq!(lexer.next_token().unwrap(), Token::LBrace); // {
+        assert_eq!(
+            lexer.next_token().unwrap(),
+            Token::StringValue("foo".to_string())
+        ); // "foo"
+        assert_eq!(lexer.next_token().unwrap(), Token::Colon); // :
+        assert_eq!(
+            lexer.next_token().unwrap(),
+            Token::NumberValue(123.0.into())
+        ); // 123
+        assert_eq!(lexer.next_token().unwrap(), Token::RBrace); // }
+        assert_eq!(lexer.next_token().unwrap(), Token::End); // end
     }
 
     #[test]
@@ -226,13 +1140,13 @@ mod tests {
             Token::LBrace,                         // {
             Token::StringValue("foo".to_string()), // "foo"
             Token::Colon,                          // :
-            Token::NumberValue(123.0),             // 123
+            Token::NumberValue(123.0.into()),      // 123
             Token::RBrace,                         // }
             Token::End,                            // end
         ];
 
         for test in tests {
-            assert_eq!(lexer.next_token(), test);
+            assert_eq!(lexer.next_token().unwrap(), test);
         }
     }
 }
@@ -1,5 +1,24 @@
+pub mod diff;
+#[cfg(feature = "encoding")]
+pub mod encoding;
+pub mod escape;
 pub mod lexer;
 pub mod node;
 pub mod parse;
+pub mod patch;
+#[cfg(feature = "serde")]
+pub mod serde;
 pub mod token;
 pub mod utility;
+pub mod validate;
+pub mod value;
+pub mod visit;
+
+pub use escape::{
+    escape_string, escape_string_ascii, escape_string_with_quote, unescape_string,
+    unescape_string_with_quote,
+};
+pub use parse::parse_str;
+#[cfg(feature = "decimal")]
+pub use rust_decimal;
+pub use validate::validate_stream;
@@ -1,7 +1,58 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt,
+};
+
+use crate::{
+    escape::escape_string,
+    parse::{ParseOptions, Parser},
+};
+
+/// A number together with the text it was written as, so that `1.0` or a
+/// long integer can be written back exactly. Numbers built from an `f64` use
+/// its shortest representation, or `Infinity`, `-Infinity` and `NaN`.
+#[derive(Debug, PartialEq, PartialOrd, Clone)]
+pub struct Number {
+    pub value: f64,
+    pub lexeme: String,
+}
+
+impl Number {
+    /// Whether this is `Infinity`, `-Infinity` or `NaN`, which JSON cannot
+    /// represent.
+    pub fn is_non_finite(&self) -> bool {
+        matches!(self.lexeme.as_str(), "Infinity" | "-Infinity" | "NaN")
+    }
+
+    /// Whether this was written without a fraction or exponent, as an
+    /// integer.
+    pub fn is_integer(&self) -> bool {
+        !self.is_non_finite() && !self.lexeme.contains(['.', 'e', 'E'])
+    }
+}
+
+impl From<f64> for Number {
+    fn from(value: f64) -> Self {
+        let lexeme = if value.is_nan() {
+            "NaN".to_string()
+        } else if value.is_infinite() {
+            let sign = if value < 0.0 { "-" } else { "" };
+            format!("{}Infinity", sign)
+        } else {
+            value.to_string()
+        };
+        Number { value, lexeme }
+    }
+}
+
 #[derive(Debug, PartialEq, PartialOrd, Clone)]
 pub enum SyntaxKind {
     StringLiteral(String),
-    NumberLiteral(f64),
+    NumberLiteral(Number),
+    /// A number kept as an exact decimal, produced when parsing with
+    /// `ParseOptions::decimal`.
+    #[cfg(feature = "decimal")]
+    DecimalLiteral(rust_decimal::Decimal),
     TrueKeyword,
     FalseKeyword,
     NullKeyword,
@@ -9,7 +60,6 @@ pub enum SyntaxKind {
     Identifier(String),
     ObjectLiteralExpression,
     ArrayLiteralExpression,
-    End,
 }
 
 #[derive(Debug, PartialEq, PartialOrd, Clone)]
@@ -22,4 +72,749 @@ impl Node {
     pub fn new(kind: SyntaxKind, children: Vec<Node>) -> Self {
         Node { kind, children }
     }
+
+    /// Drops the tree without recursing. Dropping a `Node` the usual way
+    /// recurses once per level of nesting, which overflows the stack on
+    /// trees nested tens of thousands deep.
+    pub fn drop_deep(mut self) {
+        let mut stack = std::mem::take(&mut self.children);
+        while let Some(mut node) = stack.pop() {
+            stack.append(&mut node.children);
+        }
+    }
+
+    /// Moves the subtree out of `self`, leaving a `null` in its place.
+    pub fn take(&mut self) -> Node {
+        self.replace(Node::new(SyntaxKind::NullKeyword, vec![]))
+    }
+
+    /// Puts `new` in place of `self` and returns the previous subtree.
+    pub fn replace(&mut self, new: Node) -> Node {
+        std::mem::replace(self, new)
+    }
+
+    /// Keeps the array elements for which `f` returns true, or the object
+    /// properties for which `f` returns true when given the property's value
+    /// (`children[1]` of the property assignment); other nodes are unchanged.
+    pub fn retain(&mut self, mut f: impl FnMut(&Node) -> bool) {
+        match self.kind {
+            SyntaxKind::ObjectLiteralExpression => {
+                self.children.retain(|property| f(&property.children[1]))
+            }
+            SyntaxKind::ArrayLiteralExpression => self.children.retain(|element| f(element)),
+            _ => {}
+        }
+    }
+
+    /// Returns the keys of an object in document order, or an empty list for
+    /// any other node.
+    pub fn keys(&self) -> Vec<&str> {
+        if self.kind != SyntaxKind::ObjectLiteralExpression {
+            return vec![];
+        }
+        self.children
+            .iter()
+            .filter_map(|property| match &property.children[0].kind {
+                SyntaxKind::Identifier(key) => Some(key.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Fails with the keys of this object that are not in `allowed`.
+    pub fn require_only_keys(&self, allowed: &[&str]) -> Result<(), Vec<String>> {
+        let unexpected: Vec<String> = self
+            .keys()
+            .into_iter()
+            .filter(|key| !allowed.contains(key))
+            .map(|key| key.to_string())
+            .collect();
+        if unexpected.is_empty() {
+            Ok(())
+        } else {
+            Err(unexpected)
+        }
+    }
+
+    /// Returns the union of the keys of every object element of an array, in
+    /// first-seen order. Non-object elements are ignored.
+    pub fn infer_columns(&self) -> Vec<String> {
+        let mut columns: Vec<String> = Vec::new();
+        if self.kind != SyntaxKind::ArrayLiteralExpression {
+            return columns;
+        }
+        for element in &self.children {
+            for key in element.keys() {
+                if !columns.iter().any(|column| column == key) {
+                    columns.push(key.to_string());
+                }
+            }
+        }
+        columns
+    }
+
+    pub fn object_to_entries_array(&self) -> Option<Node> {
+        if self.kind != SyntaxKind::ObjectLiteralExpression {
+            return None;
+        }
+        let mut entries = Vec::new();
+        for property in &self.children {
+            let key = match &property.children[0].kind {
+                SyntaxKind::Identifier(key) => key.clone(),
+                _ => return None,
+            };
+            entries.push(Node::new(
+                SyntaxKind::ArrayLiteralExpression,
+                vec![
+                    Node::new(SyntaxKind::StringLiteral(key), vec![]),
+                    property.children[1].clone(),
+                ],
+            ));
+        }
+        Some(Node::new(SyntaxKind::ArrayLiteralExpression, entries))
+    }
+
+    pub fn entries_array_to_object(&self) -> Option<Node> {
+        if self.kind != SyntaxKind::ArrayLiteralExpression {
+            return None;
+        }
+        let mut property_assignments = Vec::new();
+        for entry in &self.children {
+            if entry.kind != SyntaxKind::ArrayLiteralExpression || entry.children.len() != 2 {
+                return None;
+            }
+            let key = match &entry.children[0].kind {
+                SyntaxKind::StringLiteral(key) => key.clone(),
+                _ => return None,
+            };
+            property_assignments.push(Node::new(
+                SyntaxKind::PropertyAssignment,
+                vec![
+                    Node::new(SyntaxKind::Identifier(key), vec![]),
+                    entry.children[1].clone(),
+                ],
+            ));
+        }
+        Some(Node::new(
+            SyntaxKind::ObjectLiteralExpression,
+            property_assignments,
+        ))
+    }
+
+    /// Resolves an RFC 6901 JSON pointer such as `/foo/0/bar` to the node it
+    /// refers to. Of repeated keys, the last one wins.
+    pub fn get_pointer(&self, pointer: &str) -> Option<&Node> {
+        let mut current = self;
+        for token in pointer_tokens(pointer)? {
+            current = current.child(&token)?;
+        }
+        Some(current)
+    }
+
+    fn child(&self, token: &str) -> Option<&Node> {
+        match self.kind {
+            SyntaxKind::ObjectLiteralExpression => self.get(token),
+            SyntaxKind::ArrayLiteralExpression => self.at(array_index(token)?),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of the member named `key` if this is an object. Of
+    /// repeated keys, the last one wins.
+    pub fn get(&self, key: &str) -> Option<&Node> {
+        if self.kind != SyntaxKind::ObjectLiteralExpression {
+            return None;
+        }
+        self.children
+            .iter()
+            .rev()
+            .find(|property| {
+                matches!(&property.children[0].kind, SyntaxKind::Identifier(name) if name == key)
+            })
+            .map(|property| &property.children[1])
+    }
+
+    /// Compares two documents as JSON values: object members may come in any
+    /// order, with the last of repeated keys counting, array order matters
+    /// and numbers compare by value, so `1.0` equals `1`.
+    pub fn semantic_eq(&self, other: &Node) -> bool {
+        match (&self.kind, &other.kind) {
+            (SyntaxKind::ObjectLiteralExpression, SyntaxKind::ObjectLiteralExpression) => {
+                let keys: BTreeSet<&str> = self.keys().into_iter().collect();
+                let other_keys: BTreeSet<&str> = other.keys().into_iter().collect();
+                keys == other_keys
+                    && keys
+                        .into_iter()
+                        .all(|key| match (self.get(key), other.get(key)) {
+                            (Some(value), Some(other_value)) => value.semantic_eq(other_value),
+                            _ => false,
+                        })
+            }
+            (SyntaxKind::ArrayLiteralExpression, SyntaxKind::ArrayLiteralExpression) => {
+                self.children.len() == other.children.len()
+                    && self
+                        .children
+                        .iter()
+                        .zip(&other.children)
+                        .all(|(element, other_element)| element.semantic_eq(other_element))
+            }
+            (SyntaxKind::NumberLiteral(a), SyntaxKind::NumberLiteral(b)) => a.value == b.value,
+            #[cfg(feature = "decimal")]
+            (SyntaxKind::DecimalLiteral(a), SyntaxKind::DecimalLiteral(b)) => a == b,
+            #[cfg(feature = "decimal")]
+            (SyntaxKind::DecimalLiteral(d), SyntaxKind::NumberLiteral(n))
+            | (SyntaxKind::NumberLiteral(n), SyntaxKind::DecimalLiteral(d)) => {
+                use rust_decimal::prelude::ToPrimitive;
+                d.to_f64() == Some(n.value)
+            }
+            (kind, other_kind) => kind == other_kind,
+        }
+    }
+
+    /// Returns the element at `index` if this is an array.
+    pub fn at(&self, index: usize) -> Option<&Node> {
+        match self.kind {
+            SyntaxKind::ArrayLiteralExpression => self.children.get(index),
+            _ => None,
+        }
+    }
+
+    /// Resolves an RFC 6901 JSON pointer to a mutable reference into the tree.
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut Node> {
+        let mut current = self;
+        for token in pointer_tokens(pointer)? {
+            current = current.child_mut(&token)?;
+        }
+        Some(current)
+    }
+
+    fn child_mut(&mut self, token: &str) -> Option<&mut Node> {
+        match self.kind {
+            SyntaxKind::ObjectLiteralExpression => self
+                .children
+                .iter_mut()
+                .rev()
+                .find(|property| {
+                    matches!(&property.children[0].kind, SyntaxKind::Identifier(key) if key == token)
+                })
+                .map(|property| &mut property.children[1]),
+            SyntaxKind::ArrayLiteralExpression => self.children.get_mut(array_index(token)?),
+            _ => None,
+        }
+    }
+
+    /// Writes the tree as compact JSON without going through a formatter.
+    /// Numbers keep the text they were parsed from; `Infinity` and `NaN`
+    /// become null.
+    pub fn to_json(&self) -> String {
+        let mut s = String::new();
+        self.write_json(&mut s);
+        s
+    }
+
+    fn write_json(&self, s: &mut String) {
+        match &self.kind {
+            SyntaxKind::StringLiteral(text) | SyntaxKind::Identifier(text) => {
+                s.push_str(&escape_string(text))
+            }
+            SyntaxKind::NumberLiteral(number) if number.is_non_finite() => s.push_str("null"),
+            SyntaxKind::NumberLiteral(number) => s.push_str(&number.lexeme),
+            #[cfg(feature = "decimal")]
+            SyntaxKind::DecimalLiteral(value) => s.push_str(&value.to_string()),
+            SyntaxKind::TrueKeyword => s.push_str("true"),
+            SyntaxKind::FalseKeyword => s.push_str("false"),
+            SyntaxKind::NullKeyword => s.push_str("null"),
+            SyntaxKind::PropertyAssignment => {
+                self.children[0].write_json(s);
+                s.push(':');
+                self.children[1].write_json(s);
+            }
+            SyntaxKind::ObjectLiteralExpression | SyntaxKind::ArrayLiteralExpression => {
+                let (open, close) = match self.kind {
+                    SyntaxKind::ObjectLiteralExpression => ('{', '}'),
+                    _ => ('[', ']'),
+                };
+                s.push(open);
+                for (index, child) in self.children.iter().enumerate() {
+                    if index > 0 {
+                        s.push(',');
+                    }
+                    child.write_json(s);
+                }
+                s.push(close);
+            }
+        }
+    }
+
+    /// Returns the JSON pointer of every node in the document, containers
+    /// included, in document order starting with `""` for the root.
+    pub fn all_pointers(&self) -> Vec<String> {
+        let mut pointers = Vec::new();
+        self.collect_pointers("", &mut pointers);
+        pointers
+    }
+
+    fn collect_pointers(&self, pointer: &str, pointers: &mut Vec<String>) {
+        pointers.push(pointer.to_string());
+        match self.kind {
+            SyntaxKind::ObjectLiteralExpression => {
+                for property in &self.children {
+                    if let SyntaxKind::Identifier(key) = &property.children[0].kind {
+                        let child_pointer = format!("{}/{}", pointer, escape_pointer_token(key));
+                        property.children[1].collect_pointers(&child_pointer, pointers);
+                    }
+                }
+            }
+            SyntaxKind::ArrayLiteralExpression => {
+                for (index, element) in self.children.iter().enumerate() {
+                    element.collect_pointers(&format!("{}/{}", pointer, index), pointers);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Lists every node by JSON pointer next to the line of `input` it starts
+    /// on, for logging. `input` and `options` should be what the tree was
+    /// parsed from and with. Nodes not found in `input` get no line, and
+    /// neither does any node when `input` does not parse with `options`.
+    pub fn debug_with_source(&self, input: &str, options: ParseOptions) -> String {
+        let mut parser = Parser::with_options(
+            input,
+            ParseOptions {
+                spans: true,
+                ..options
+            },
+        );
+        // repeated keys share a pointer and are paired in document order
+        let mut starts: HashMap<&str, Vec<usize>> = HashMap::new();
+        if let Ok(tree) = parser.try_parse() {
+            tree.drop_deep();
+            for span in parser.spans().iter().rev() {
+                starts
+                    .entry(span.pointer.as_str())
+                    .or_default()
+                    .push(span.range.start);
+            }
+        }
+
+        let line_starts: Vec<usize> = std::iter::once(0)
+            .chain(input.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
+        let pointers = self.all_pointers();
+        let label = |pointer: &str| match pointer {
+            "" => "(root)".to_string(),
+            pointer => pointer.to_string(),
+        };
+        let width = pointers
+            .iter()
+            .map(|pointer| label(pointer).chars().count())
+            .max()
+            .unwrap_or(0);
+        let digits = line_starts.len().to_string().len();
+        let mut s = String::new();
+        for pointer in &pointers {
+            let start = starts
+                .get_mut(pointer.as_str())
+                .and_then(|starts| starts.pop());
+            let line = match start {
+                Some(start) => {
+                    let line = line_starts.partition_point(|&line_start| line_start <= start);
+                    let end = line_starts.get(line).map_or(input.len(), |end| end - 1);
+                    let text = input[line_starts[line - 1]..end].trim_end_matches('\r');
+                    format!("{:>digits$} | {}", line, text)
+                }
+                None => format!("{:>digits$} |", "?"),
+            };
+            s.push_str(&format!("{:<width$} {}\n", label(pointer), line));
+        }
+        s
+    }
+
+    /// Returns the JSON pointers of every object key rejected by `pred`.
+    pub fn check_keys(&self, pred: impl Fn(&str) -> bool) -> Vec<String> {
+        let mut failures = Vec::new();
+        self.collect_failing_keys(&pred, "", &mut failures);
+        failures
+    }
+
+    fn collect_failing_keys(
+        &self,
+        pred: &impl Fn(&str) -> bool,
+        pointer: &str,
+        failures: &mut Vec<String>,
+    ) {
+        match self.kind {
+            SyntaxKind::ObjectLiteralExpression => {
+                for property in &self.children {
+                    if let SyntaxKind::Identifier(key) = &property.children[0].kind {
+                        let child_pointer = format!("{}/{}", pointer, escape_pointer_token(key));
+                        if !pred(key) {
+                            failures.push(child_pointer.clone());
+                        }
+                        property.children[1].collect_failing_keys(pred, &child_pointer, failures);
+                    }
+                }
+            }
+            SyntaxKind::ArrayLiteralExpression => {
+                for (index, element) in self.children.iter().enumerate() {
+                    let child_pointer = format!("{}/{}", pointer, index);
+                    element.collect_failing_keys(pred, &child_pointer, failures);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Writes the node as compact JSON, see `Node::to_json`.
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.to_json())
+    }
+}
+
+pub fn escape_pointer_token(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+pub fn unescape_pointer_token(token: &str) -> String {
+    token.replace("~1", "/").replace("~0", "~")
+}
+
+/// Splits a JSON pointer into unescaped reference tokens, or None if it is
+/// neither empty nor starts with `/`.
+pub(crate) fn pointer_tokens(pointer: &str) -> Option<Vec<String>> {
+    if pointer.is_empty() {
+        return Some(vec![]);
+    }
+    let rest = pointer.strip_prefix('/')?;
+    Some(rest.split('/').map(unescape_pointer_token).collect())
+}
+
+pub(crate) fn array_index(token: &str) -> Option<usize> {
+    let is_canonical = token == "0" || !token.starts_with('0');
+    if !is_canonical || !token.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    token.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::Parser;
+
+    #[test]
+    fn test_all_pointers() {
+        let cases = vec![
+            (r#"[]"#, vec![""]),
+            (
+                r#"{"a": {"b": [1, {"c": null}]}, "d/e": [], "f~": true}"#,
+                vec![
+                    "", "/a", "/a/b", "/a/b/0", "/a/b/1", "/a/b/1/c", "/d~1e", "/f~0",
+                ],
+            ),
+            (r#"[[1], 2]"#, vec!["", "/0", "/0/0", "/1"]),
+        ];
+
+        for (input, expected) in cases {
+            let node = Parser::new(input).parse();
+            assert_eq!(node.all_pointers(), expected);
+        }
+    }
+
+    #[test]
+    fn test_debug_with_source() {
+        let input = "{\r\n  \"name\": \"x\",\r\n  \"tags\": [1,\r\n    2]\r\n}";
+        let node = Parser::new(input).parse();
+        assert_eq!(
+            node.debug_with_source(input, ParseOptions::default()),
+            concat!(
+                "(root)  1 | {\n",
+                "/name   2 |   \"name\": \"x\",\n",
+                "/tags   3 |   \"tags\": [1,\n",
+                "/tags/0 3 |   \"tags\": [1,\n",
+                "/tags/1 4 |     2]\n",
+            )
+        );
+
+        // repeated keys get a line each, nodes missing from the input get none
+        let input = "{\"a\": 1,\n\"a\": 2}";
+        let mut node = Parser::new(input).parse();
+        node.children
+            .push(Parser::new(r#"{"b": 3}"#).parse().children.remove(0));
+        assert_eq!(
+            node.debug_with_source(input, ParseOptions::default()),
+            "(root) 1 | {\"a\": 1,\n/a     1 | {\"a\": 1,\n/a     2 | \"a\": 2}\n/b     ? |\n"
+        );
+
+        // lenient documents read with their own options, or without lines
+        // when given options they do not parse with
+        let input = "{1: undefined}";
+        let lenient = ParseOptions {
+            lenient: true,
+            ..Default::default()
+        };
+        let node = Parser::with_options(input, lenient).parse();
+        assert_eq!(
+            node.debug_with_source(input, lenient),
+            "(root) 1 | {1: undefined}\n/1     1 | {1: undefined}\n"
+        );
+        assert_eq!(
+            node.debug_with_source(input, ParseOptions::default()),
+            "(root) ? |\n/1     ? |\n"
+        );
+
+        // nesting past the default max_depth
+        let input = format!("{}{}", "[".repeat(200), "]".repeat(200));
+        let deep = ParseOptions {
+            max_depth: 256,
+            ..Default::default()
+        };
+        let node = Parser::with_options(&input, deep).parse();
+        let with_lines = node.debug_with_source(&input, deep);
+        assert!(with_lines.lines().all(|line| line.contains(" 1 | [[[")));
+        let without_lines = node.debug_with_source(&input, ParseOptions::default());
+        assert!(without_lines.lines().all(|line| line.ends_with("? |")));
+        node.drop_deep();
+    }
+
+    #[test]
+    fn test_to_json() {
+        let cases = vec![
+            ("null", "null"),
+            ("[]", "[]"),
+            (
+                r#"{ "a": [1.0, -2.50e3, true, false, null], "b": {"c\"d": "e\nf\u0001"}, "g": {} }"#,
+                r#"{"a":[1.0,-2.50e3,true,false,null],"b":{"c\"d":"e\nf\u0001"},"g":{}}"#,
+            ),
+            (r#"[[[1]], {"a": [{}]}]"#, r#"[[[1]],{"a":[{}]}]"#),
+        ];
+
+        for (input, expected) in cases {
+            let node = Parser::new(input).parse();
+            assert_eq!(node.to_json(), expected);
+            assert_eq!(node.to_string(), expected);
+            assert_eq!(Parser::new(expected).parse(), node);
+        }
+
+        let node = Node::new(
+            SyntaxKind::ArrayLiteralExpression,
+            vec![Node::new(
+                SyntaxKind::NumberLiteral(f64::NAN.into()),
+                vec![],
+            )],
+        );
+        assert_eq!(node.to_json(), "[null]");
+    }
+
+    #[test]
+    fn test_object_to_entries_array() {
+        let cases = vec![
+            (
+                r#"{"a": 1, "b": [true]}"#,
+                Some(r#"[["a", 1], ["b", [true]]]"#),
+            ),
+            (r#"{}"#, Some(r#"[]"#)),
+            (r#"[1, 2]"#, None),
+        ];
+
+        for (input, expected) in cases {
+            let node = Parser::new(input).parse();
+            let expected = expected.map(|expected| Parser::new(expected).parse());
+            assert_eq!(node.object_to_entries_array(), expected);
+        }
+    }
+
+    #[test]
+    fn test_entries_array_to_object() {
+        let cases = vec![
+            (
+                r#"[["a", 1], ["b", [true]]]"#,
+                Some(r#"{"a": 1, "b": [true]}"#),
+            ),
+            (r#"[]"#, Some(r#"{}"#)),
+            (r#"[["a", 1, 2]]"#, None),
+            (r#"[[1, "a"]]"#, None),
+            (r#"{"a": 1}"#, None),
+        ];
+
+        for (input, expected) in cases {
+            let node = Parser::new(input).parse();
+            let expected = expected.map(|expected| Parser::new(expected).parse());
+            assert_eq!(node.entries_array_to_object(), expected);
+        }
+    }
+
+    #[test]
+    fn test_entries_round_trip() {
+        let object = Parser::new(r#"{"a": 1, "b": {"c": null}}"#).parse();
+        let entries = object.object_to_entries_array().unwrap();
+        assert_eq!(entries.entries_array_to_object(), Some(object));
+
+        let entries = Parser::new(r#"[["a", "x"], ["b", [1, 2]]]"#).parse();
+        let object = entries.entries_array_to_object().unwrap();
+        assert_eq!(object.object_to_entries_array(), Some(entries));
+    }
+
+    #[test]
+    fn test_check_keys() {
+        let input = r#"{"first_name": "a", "lastName": "b", "tags": [{"is_ok": true, "isBad": false}], "a/b": {"c~d": 1}}"#;
+        let node = Parser::new(input).parse();
+        let is_snake_case = |key: &str| key.chars().all(|c| c.is_ascii_lowercase() || c == '_');
+        assert_eq!(
+            node.check_keys(is_snake_case),
+            vec!["/lastName", "/tags/0/isBad", "/a~1b", "/a~1b/c~0d"]
+        );
+        assert_eq!(node.check_keys(|_| true), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_take_and_replace() {
+        let mut node = Parser::new(r#"{"a": [1, 2], "b": null}"#).parse();
+        let array = node.children[0].children[1].take();
+        assert_eq!(node, Parser::new(r#"{"a": null, "b": null}"#).parse());
+        assert_eq!(array, Parser::new(r#"[1, 2]"#).parse());
+
+        let previous = node.children[1].children[1].replace(array);
+        assert_eq!(previous, Node::new(SyntaxKind::NullKeyword, vec![]));
+        assert_eq!(node, Parser::new(r#"{"a": null, "b": [1, 2]}"#).parse());
+    }
+
+    #[test]
+    fn test_keys() {
+        let node = Parser::new(r#"{"b": 1, "a": {"c": 2}}"#).parse();
+        assert_eq!(node.keys(), vec!["b", "a"]);
+        assert_eq!(Parser::new("[1]").parse().keys(), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn test_infer_columns() {
+        let input =
+            r#"[{"id": 1, "name": "a"}, {"id": 2, "email": "b"}, 3, {"name": "c", "age": 4}]"#;
+        let node = Parser::new(input).parse();
+        assert_eq!(node.infer_columns(), vec!["id", "name", "email", "age"]);
+        assert_eq!(
+            Parser::new(r#"{"id": 1}"#).parse().infer_columns(),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn test_retain() {
+        let mut node = Parser::new(r#"{"a": null, "b": 1, "c": null, "d": [null]}"#).parse();
+        node.retain(|value| value.kind != SyntaxKind::NullKeyword);
+        assert_eq!(node, Parser::new(r#"{"b": 1, "d": [null]}"#).parse());
+
+        let mut node = Parser::new(r#"[1, 2, 3, 4, 5, "6"]"#).parse();
+        node.retain(|element| match element.kind {
+            SyntaxKind::NumberLiteral(ref number) => number.value % 2.0 != 0.0,
+            _ => true,
+        });
+        assert_eq!(node, Parser::new(r#"[1, 3, 5, "6"]"#).parse());
+    }
+
+    #[test]
+    fn test_require_only_keys() {
+        let node = Parser::new(r#"{"name": "a", "verison": 1, "tags": {"x": 1}}"#).parse();
+        assert_eq!(
+            node.require_only_keys(&["name", "version", "tags"]),
+            Err(vec!["verison".to_string()])
+        );
+        assert_eq!(node.require_only_keys(&["name", "verison", "tags"]), Ok(()));
+    }
+
+    #[test]
+    fn test_get_and_at() {
+        let node = Parser::new(r#"{"a": [10, {"b": null}], "c": 1, "c": 2}"#).parse();
+        let cases = vec![
+            (node.get("a").and_then(|a| a.at(0)), Some("10")),
+            (node.get("a").and_then(|a| a.at(1)), Some(r#"{"b":null}"#)),
+            (node.get("a").and_then(|a| a.at(2)), None),
+            (node.get("c"), Some("2")),
+            (node.get("missing"), None),
+            (node.at(0), None),
+            (node.get("a").and_then(|a| a.get("0")), None),
+        ];
+
+        for (found, expected) in cases {
+            assert_eq!(found.map(Node::to_json).as_deref(), expected);
+        }
+    }
+
+    #[test]
+    fn test_semantic_eq() {
+        let cases = vec![
+            (r#"{"a": 1, "b": 2}"#, r#"{"b": 2, "a": 1}"#, true),
+            (
+                r#"{"a": {"x": [1, {"y": null, "z": true}]}}"#,
+                r#"{"a": {"x": [1, {"z": true, "y": null}]}}"#,
+                true,
+            ),
+            (r#"{"a": 1, "a": 2}"#, r#"{"a": 2}"#, true),
+            (r#"{"a": 2, "a": 1}"#, r#"{"a": 2}"#, false),
+            (r#"{"a": 1}"#, r#"{"a": 1, "b": 2}"#, false),
+            (r#"[1, 2]"#, r#"[2, 1]"#, false),
+            (r#"[1, 2]"#, r#"[1, 2, 3]"#, false),
+            (r#"[1.0, 1e2]"#, r#"[1, 100]"#, true),
+            (r#""1""#, r#"1"#, false),
+            (r#"{}"#, r#"[]"#, false),
+        ];
+
+        for (a, b, expected) in cases {
+            let (a, b) = (Parser::new(a).parse(), Parser::new(b).parse());
+            assert_eq!(a.semantic_eq(&b), expected, "{} {}", a, b);
+            assert_eq!(b.semantic_eq(&a), expected, "{} {}", b, a);
+        }
+    }
+
+    #[test]
+    fn test_get_pointer() {
+        let node = Parser::new(
+            r#"{"foo": [{"bar": true}, 2], "a/b": {"m~n": 3}, "": 4, "dup": 1, "dup": 5}"#,
+        )
+        .parse();
+        let cases = vec![
+            ("", Some(node.to_json())),
+            ("/foo/0/bar", Some("true".to_string())),
+            ("/foo/1", Some("2".to_string())),
+            ("/a~1b/m~0n", Some("3".to_string())),
+            ("/", Some("4".to_string())),
+            ("/dup", Some("5".to_string())),
+            ("/foo/2", None),
+            ("/foo/-", None),
+            ("/foo/01", None),
+            ("/foo/0/bar/baz", None),
+            ("/missing", None),
+            ("foo", None),
+        ];
+
+        for (pointer, expected) in cases {
+            let found = node.get_pointer(pointer).map(Node::to_json);
+            assert_eq!(found, expected, "{}", pointer);
+        }
+    }
+
+    #[test]
+    fn test_pointer_mut() {
+        let mut node =
+            Parser::new(r#"{"package": {"meta": [{"version": 1}]}, "a/b": {"~": 2}}"#).parse();
+        let version = node.pointer_mut("/package/meta/0/version").unwrap();
+        *version = Node::new(SyntaxKind::NumberLiteral(2.0.into()), vec![]);
+        assert_eq!(
+            node,
+            Parser::new(r#"{"package": {"meta": [{"version": 2}]}, "a/b": {"~": 2}}"#).parse()
+        );
+
+        assert_eq!(
+            node.pointer_mut("/a~1b/~0").map(|node| node.kind.clone()),
+            Some(SyntaxKind::NumberLiteral(2.0.into()))
+        );
+        assert!(node.pointer_mut("").is_some());
+        assert!(node.pointer_mut("/package/meta/1").is_none());
+        assert!(node.pointer_mut("/package/meta/01").is_none());
+        assert!(node.pointer_mut("/missing").is_none());
+        assert!(node.pointer_mut("package").is_none());
+    }
 }
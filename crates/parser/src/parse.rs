@@ -1,24 +1,480 @@
+use std::{
+    fmt,
+    ops::Range,
+    time::{Duration, Instant},
+};
+
 use crate::{
-    lexer::Lexer,
-    node::{Node, SyntaxKind},
+    lexer::{LexError, Lexer},
+    node::{escape_pointer_token, Node, SyntaxKind},
     token::Token,
-    utility::PeekableIter,
+    value::Value,
 };
 
-pub struct Parser {
-    token_stream: PeekableIter<Token>,
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct ParseTimings {
+    pub lex: Duration,
+    pub parse: Duration,
+}
+
+impl ParseTimings {
+    pub fn total(&self) -> Duration {
+        self.lex + self.parse
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct ParseOptions {
+    /// Accepts near-JSON input such as `undefined`, recording a warning for
+    /// every normalization applied.
+    pub lenient: bool,
+    /// Skips `//` and `/* */` comments, as found in JSONC files.
+    pub allow_comments: bool,
+    /// Accepts a single comma after the last element of an array or object.
+    pub allow_trailing_commas: bool,
+    /// Accepts bare identifiers such as `{foo: 1}` as object keys, including
+    /// words like `true` that are keywords elsewhere.
+    pub allow_unquoted_keys: bool,
+    /// Accepts `'`-delimited strings, keys included.
+    pub allow_single_quotes: bool,
+    /// Accepts `Infinity`, `-Infinity` and `NaN` as numbers.
+    pub allow_non_finite: bool,
+    /// Keeps numbers as exact `rust_decimal::Decimal` values instead of `f64`.
+    /// Only takes effect with the `decimal` feature enabled.
+    pub decimal: bool,
+    /// Containers may be nested at most this deep. Parsing itself does not
+    /// recurse, but formatting, dropping and most tree walks still do; use
+    /// `Node::drop_deep` to release trees nested deeper than the default.
+    pub max_depth: usize,
+    /// Records where each value was read from, see `Parser::spans`. Together
+    /// with `allow_comments`, also keeps the comments, see `Parser::comments`.
+    pub spans: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            lenient: false,
+            allow_comments: false,
+            allow_trailing_commas: false,
+            allow_unquoted_keys: false,
+            allow_single_quotes: false,
+            allow_non_finite: false,
+            decimal: false,
+            max_depth: 128,
+            spans: false,
+        }
+    }
+}
+
+/// The byte range in the input of the value at `pointer`, from its first
+/// character to just past its last.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Span {
+    pub pointer: String,
+    pub range: Range<usize>,
+}
+
+/// Where a comment sits relative to the value it is attached to.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum CommentPlacement {
+    /// On lines of its own before the value, or before the member holding it.
+    Above,
+    /// At the end of the line the value ends on.
+    EndOfLine,
+    /// On lines of its own after the value.
+    Below,
+    /// On lines of its own inside the object or array, after its last member.
+    Inside,
+}
+
+/// A comment and the value, by JSON pointer, it was found next to.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Comment {
+    /// The comment as written, with its `//` or `/* */` delimiters.
+    pub text: String,
+    pub pointer: String,
+    pub placement: CommentPlacement,
+}
+
+enum Frame {
+    Object {
+        property_assignments: Vec<Node>,
+        property_name: Option<String>,
+        /// Keys read so far, flagged when they were converted from a
+        /// non-string token in lenient mode.
+        seen_keys: Vec<(String, bool)>,
+    },
+    Array {
+        elements: Vec<Node>,
+    },
+}
+
+impl Frame {
+    fn into_node(self) -> Node {
+        match self {
+            Frame::Object {
+                property_assignments,
+                ..
+            } => Node::new(SyntaxKind::ObjectLiteralExpression, property_assignments),
+            Frame::Array { elements } => Node::new(SyntaxKind::ArrayLiteralExpression, elements),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ParseErrorKind {
+    /// The input could not be tokenized.
+    Lex,
+    /// A token appeared where it is not allowed.
+    Syntax,
+    /// Containers were nested deeper than `ParseOptions::max_depth`.
+    DepthExceeded,
+}
+
+/// A parse failure and the index of the token it happened at. Lexing errors
+/// report the index the unreadable token would have had.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub message: String,
+    pub index: usize,
+    /// Byte offset in the input of that token, or of the unreadable text.
+    pub offset: usize,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (token {})", self.message, self.index)
+    }
 }
 
-impl Parser {
-    pub fn new(input: &str) -> Self {
-        let mut lexer = Lexer::new(input);
-        let tokens = lexer.tokenize();
-        let token_stream = tokens.into_iter().peekable();
-        Parser { token_stream }
+impl std::error::Error for ParseError {}
+
+impl ParseError {
+    /// The 1-based line and column of the error in `input`, counting the
+    /// column in characters. An error at the end of input is placed just past
+    /// the last non-blank character.
+    pub fn line_col(&self, input: &str) -> (usize, usize) {
+        let offset = self.display_offset(input);
+        let line_start = input[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let line = input[..line_start].matches('\n').count() + 1;
+        (line, input[line_start..offset].chars().count() + 1)
+    }
+
+    fn display_offset(&self, input: &str) -> usize {
+        let offset = self.offset.min(input.len());
+        if input[offset..].trim().is_empty() {
+            input.trim_end().len()
+        } else {
+            offset
+        }
+    }
+}
+
+/// Renders `err` with the line of `input` it is on and a caret under the
+/// offending column, in the style of rustc. An error at the end of input
+/// points just past the last non-blank character.
+pub fn render_error(input: &str, err: &ParseError) -> String {
+    let offset = err.display_offset(input);
+    let (line_number, column) = err.line_col(input);
+    let line_start = input[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = input[offset..]
+        .find('\n')
+        .map_or(input.len(), |i| offset + i);
+    let line = input[line_start..line_end].trim_end_matches('\r');
+    // keep tabs so the caret lines up however they are displayed
+    let caret_indent: String = input[line_start..offset]
+        .chars()
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    let gutter = " ".repeat(line_number.to_string().len());
+    format!(
+        "error: {}\n{}--> {}:{}\n{} |\n{} | {}\n{} | {}^",
+        err.message, gutter, line_number, column, gutter, line_number, line, gutter, caret_indent
+    )
+}
+
+/// Pulls tokens one at a time as the parser asks for them. A lex error ends
+/// the stream with `Token::End` and is kept to be reported instead of
+/// whatever the parser made of that end.
+struct TokenStream<'a> {
+    tokens: Box<dyn TokenSource + 'a>,
+    peeked: Option<Token>,
+    /// Index of the next token to be consumed.
+    position: usize,
+    /// Byte offset of the peeked token, or of the text that failed to lex.
+    offset: usize,
+    /// Byte offset just past the peeked token.
+    end: usize,
+    /// Byte offset just past the last token consumed.
+    last_end: usize,
+    /// The lex error and the index the unreadable token would have had.
+    lex_error: Option<(LexError, usize)>,
+}
+
+/// Where a `TokenStream` pulls from. Only a lexer knows about lines, so
+/// pre-lexed tokens cannot skip past a bad one.
+pub(crate) trait TokenSource: Iterator<Item = Result<Token, LexError>> {
+    fn after_line_break(&self) -> bool {
+        false
+    }
+
+    /// Byte offset of the last token pulled. Pre-lexed tokens carry no
+    /// positions and report 0.
+    fn token_start(&self) -> usize {
+        0
+    }
+
+    /// Byte offset just past the last token pulled, 0 for pre-lexed tokens.
+    fn token_end(&self) -> usize {
+        0
+    }
+
+    fn skip_line(&mut self) {}
+
+    fn comments(&self) -> &[Range<usize>] {
+        &[]
+    }
+}
+
+impl TokenSource for Lexer<'_> {
+    fn after_line_break(&self) -> bool {
+        Lexer::after_line_break(self)
+    }
+
+    fn token_start(&self) -> usize {
+        Lexer::token_start(self)
+    }
+
+    fn token_end(&self) -> usize {
+        Lexer::token_end(self)
+    }
+
+    fn skip_line(&mut self) {
+        Lexer::skip_line(self)
+    }
+
+    fn comments(&self) -> &[Range<usize>] {
+        Lexer::comments(self)
+    }
+}
+
+/// Tokens lexed up front, as `Parser::from_tokens` takes them.
+type BufferedTokens =
+    std::iter::Map<std::vec::IntoIter<Token>, fn(Token) -> Result<Token, LexError>>;
+
+impl TokenSource for BufferedTokens {}
+
+impl<'a> TokenStream<'a> {
+    fn new(tokens: Box<dyn TokenSource + 'a>) -> Self {
+        TokenStream {
+            tokens,
+            peeked: None,
+            position: 0,
+            offset: 0,
+            end: 0,
+            last_end: 0,
+            lex_error: None,
+        }
+    }
+
+    fn peek(&mut self) -> Option<&Token> {
+        if self.peeked.is_none() {
+            self.peeked = match self.tokens.next()? {
+                Ok(token) => {
+                    self.offset = self.tokens.token_start();
+                    self.end = self.tokens.token_end();
+                    Some(token)
+                }
+                Err(e) => {
+                    self.offset = e.offset();
+                    self.lex_error = Some((e, self.position));
+                    Some(Token::End)
+                }
+            };
+        }
+        self.peeked.as_ref()
+    }
+
+    /// Drops the peeked token and any lex error, resuming on the next line.
+    fn skip_line(&mut self) {
+        self.peeked = None;
+        self.lex_error = None;
+        self.tokens.skip_line();
+    }
+}
+
+impl Iterator for TokenStream<'_> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        self.peek();
+        let token = self.peeked.take()?;
+        self.position += 1;
+        self.last_end = self.end;
+        Some(token)
+    }
+}
+
+pub struct Parser<'a> {
+    /// The text being parsed, empty for pre-lexed tokens.
+    input: &'a str,
+    token_stream: TokenStream<'a>,
+    options: ParseOptions,
+    warnings: Vec<String>,
+    /// Errors skipped over by `parse_recover`; `None` outside of it.
+    errors: Option<Vec<ParseError>>,
+    spans: Vec<Span>,
+    /// Indices in `spans` of the values begun but not yet finished.
+    open_spans: Vec<usize>,
+    /// Drop values once read instead of building the tree, for `validate`.
+    discard_values: bool,
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Parser::with_options(input, ParseOptions::default())
+    }
+
+    pub fn with_options(input: &'a str, options: ParseOptions) -> Self {
+        #[cfg(feature = "decimal")]
+        let lexer = Lexer::with_decimal(input, options.decimal);
+        #[cfg(not(feature = "decimal"))]
+        let lexer = Lexer::new(input);
+        let lexer = lexer
+            .allow_comments(options.allow_comments)
+            .allow_identifiers(options.allow_unquoted_keys)
+            .allow_single_quotes(options.allow_single_quotes)
+            .allow_non_finite(options.allow_non_finite);
+        Parser {
+            input,
+            token_stream: TokenStream::new(Box::new(lexer)),
+            options,
+            warnings: Vec::new(),
+            errors: None,
+            spans: Vec::new(),
+            open_spans: Vec::new(),
+            discard_values: false,
+        }
+    }
+
+    fn from_tokens(tokens: Vec<Token>, options: ParseOptions) -> Self {
+        Parser::from_source(
+            Box::new(
+                tokens
+                    .into_iter()
+                    .map(Ok as fn(Token) -> Result<Token, LexError>),
+            ),
+            options,
+        )
+    }
+
+    /// A parser over tokens that do not come from a `&str` held here, so
+    /// spans and comments are unavailable.
+    pub(crate) fn from_source(tokens: Box<dyn TokenSource + 'a>, options: ParseOptions) -> Self {
+        Parser {
+            input: "",
+            token_stream: TokenStream::new(tokens),
+            options,
+            warnings: Vec::new(),
+            errors: None,
+            spans: Vec::new(),
+            open_spans: Vec::new(),
+            discard_values: false,
+        }
+    }
+
+    fn syntax_error(&self, message: &str) -> ParseError {
+        ParseError {
+            kind: ParseErrorKind::Syntax,
+            message: message.to_string(),
+            index: self.token_stream.position,
+            offset: self.token_stream.offset,
+        }
+    }
+
+    /// Opens a container on `stack`, failing once `max_depth` would be
+    /// exceeded.
+    fn push_frame(&mut self, stack: &mut Vec<Frame>, frame: Frame) -> Result<(), ParseError> {
+        if stack.len() >= self.options.max_depth {
+            return Err(ParseError {
+                kind: ParseErrorKind::DepthExceeded,
+                message: format!(
+                    "Maximum nesting depth of {} exceeded",
+                    self.options.max_depth
+                ),
+                index: self.token_stream.position,
+                offset: self.token_stream.offset,
+            });
+        }
+        self.next_token();
+        stack.push(frame);
+        Ok(())
+    }
+
+    fn next_token(&mut self) -> Option<Token> {
+        self.token_stream.next()
+    }
+
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Where each value of the last document read came from, in document
+    /// order, when parsing with `ParseOptions::spans`. Not recorded by
+    /// `parse_recover`.
+    pub fn spans(&self) -> &[Span] {
+        &self.spans
+    }
+
+    /// The comments of the document read by `parse` or `try_parse`, each
+    /// attached to the nearest value, when parsing with both `allow_comments`
+    /// and `spans`.
+    pub fn comments(&self) -> Vec<Comment> {
+        attach_comments(self.input, &self.spans, self.token_stream.tokens.comments())
+    }
+
+    /// Starts the span of the value about to be read into the innermost
+    /// container of `stack`.
+    fn open_span(&mut self, stack: &[Frame]) {
+        if !self.options.spans || self.errors.is_some() {
+            return;
+        }
+        self.token_stream.peek();
+        let pointer = match (self.open_spans.last(), stack.last()) {
+            (Some(&parent), Some(Frame::Object { property_name, .. })) => format!(
+                "{}/{}",
+                self.spans[parent].pointer,
+                escape_pointer_token(property_name.as_deref().unwrap_or_default())
+            ),
+            (Some(&parent), Some(Frame::Array { elements })) => {
+                format!("{}/{}", self.spans[parent].pointer, elements.len())
+            }
+            _ => String::new(),
+        };
+        let start = self.token_stream.offset;
+        self.open_spans.push(self.spans.len());
+        self.spans.push(Span {
+            pointer,
+            range: start..start,
+        });
+    }
+
+    /// Ends the span of the value just read.
+    fn close_span(&mut self) {
+        if let Some(index) = self.open_spans.pop() {
+            self.spans[index].range.end = self.token_stream.last_end;
+        }
+    }
+
+    /// The error that stopped tokenizing, once parsing has reached it.
+    pub fn lex_error(&self) -> Option<&LexError> {
+        self.token_stream.lex_error.as_ref().map(|(e, _)| e)
     }
 
     fn consume_string(&mut self) -> Node {
-        let token = self.token_stream.next();
+        let token = self.next_token();
         match token {
             Some(Token::StringValue(value)) => Node::new(SyntaxKind::StringLiteral(value), vec![]),
             Some(illigal_token) => panic!("Unexpected token: {:?}", illigal_token),
@@ -27,118 +483,604 @@ impl Parser {
     }
 
     fn consume_number(&mut self) -> Node {
-        let token = self.token_stream.next();
+        let token = self.next_token();
         match token {
             Some(Token::NumberValue(value)) => Node::new(SyntaxKind::NumberLiteral(value), vec![]),
+            #[cfg(feature = "decimal")]
+            Some(Token::DecimalValue(value)) => {
+                Node::new(SyntaxKind::DecimalLiteral(value), vec![])
+            }
             Some(illegal_token) => panic!("Unexpected token: {:?}", illegal_token),
             None => panic!("Unexpected end of input"),
         }
     }
 
     fn consume_keyword(&mut self) -> Node {
-        let token = self.token_stream.next();
+        let token = self.next_token();
         match token {
             Some(Token::BooleanValue(true)) => Node::new(SyntaxKind::TrueKeyword, vec![]),
             Some(Token::BooleanValue(false)) => Node::new(SyntaxKind::FalseKeyword, vec![]),
             Some(Token::NullValue) => Node::new(SyntaxKind::NullKeyword, vec![]),
+            Some(Token::UndefinedValue) => {
+                self.warnings
+                    .push("`undefined` is not valid JSON, replaced with null".to_string());
+                Node::new(SyntaxKind::NullKeyword, vec![])
+            }
             Some(illigal_token) => panic!("Unexpected token: {:?}", illigal_token),
             None => unreachable!("Unexpected token of input"),
         }
     }
 
-    fn consume_property_assignment(&mut self) -> Result<Node, String> {
-        let property_name = match self.token_stream.peek() {
-            Some(Token::StringValue(s)) => s.clone(),
-            _ => return Err("Unexpected Identifier".to_string()),
+    /// Reads a key and its colon, returning the key and whether it was
+    /// converted from a non-string token in lenient mode.
+    fn consume_property_name(&mut self) -> Result<(String, bool), ParseError> {
+        let unquoted = self.options.allow_unquoted_keys;
+        let (property_name, converted) = match self.token_stream.peek() {
+            Some(Token::StringValue(s)) => (s.clone(), false),
+            Some(Token::Identifier(s)) if unquoted => (s.clone(), false),
+            Some(Token::BooleanValue(b)) if unquoted => (b.to_string(), false),
+            Some(Token::NullValue) if unquoted => ("null".to_string(), false),
+            Some(Token::UndefinedValue) if unquoted => ("undefined".to_string(), false),
+            Some(Token::NumberValue(n)) if self.options.lenient => (n.lexeme.clone(), true),
+            #[cfg(feature = "decimal")]
+            Some(Token::DecimalValue(d)) if self.options.lenient => (d.to_string(), true),
+            Some(Token::BooleanValue(b)) if self.options.lenient => (b.to_string(), true),
+            _ => return Err(self.syntax_error("Unexpected token of input")),
         };
-        self.token_stream.next();
-        self.token_stream.next();
-        match self.consume_value() {
-            Ok(value) => Ok(Node::new(
-                SyntaxKind::PropertyAssignment,
-                vec![
-                    Node::new(SyntaxKind::Identifier(property_name), vec![]),
-                    value,
-                ],
-            )),
-            Err(e) => Err(e),
+        if converted {
+            self.warnings
+                .push(format!("Non-string key converted to \"{}\"", property_name));
+        }
+        self.next_token();
+        match self.token_stream.peek() {
+            Some(Token::Colon) => {
+                self.next_token();
+                Ok((property_name, converted))
+            }
+            _ => Err(self.syntax_error("Expected `:`")),
+        }
+    }
+
+    fn check_duplicate_key(
+        &mut self,
+        seen_keys: &mut Vec<(String, bool)>,
+        name: &str,
+        normalized: bool,
+    ) {
+        if let Some((_, seen_normalized)) = seen_keys.iter().find(|(key, _)| key == name) {
+            if normalized || *seen_normalized {
+                self.warnings.push(format!(
+                    "Key \"{}\" collides with another key after converting non-string keys",
+                    name
+                ));
+            } else {
+                self.warnings.push(format!("Duplicate key \"{}\"", name));
+            }
+        }
+        seen_keys.push((name.to_string(), normalized));
+    }
+
+    /// Parses a value using an explicit stack of open containers instead of
+    /// recursion, so deeply nested input cannot overflow the call stack.
+    fn consume_value(&mut self) -> Result<Node, ParseError> {
+        self.spans.clear();
+        self.open_spans.clear();
+        let mut stack: Vec<Frame> = Vec::new();
+        let result = self.consume_value_with(&mut stack);
+        // containers left open by an error may hold deeply nested values
+        for frame in stack {
+            frame.into_node().drop_deep();
         }
+        result
     }
 
-    fn consume_object(&mut self) -> Result<Node, String> {
-        let mut property_assignments = Vec::new();
-        self.token_stream.next();
+    fn consume_value_with(&mut self, stack: &mut Vec<Frame>) -> Result<Node, ParseError> {
+        let mut skipped = false;
         loop {
-            match self.token_stream.peek() {
-                Some(Token::RBrace) => {
-                    self.token_stream.next();
-                    break;
+            self.open_span(stack);
+            let mut completed = match self.token_stream.peek() {
+                Some(Token::StringValue(_)) => Some(self.consume_string()),
+                Some(Token::NumberValue(_)) => Some(self.consume_number()),
+                #[cfg(feature = "decimal")]
+                Some(Token::DecimalValue(_)) => Some(self.consume_number()),
+                Some(Token::BooleanValue(_)) | Some(Token::NullValue) => {
+                    Some(self.consume_keyword())
                 }
-                Some(Token::StringValue(_)) => match self.consume_property_assignment() {
-                    Ok(property_assignment) => property_assignments.push(property_assignment),
-                    Err(e) => return Err(e),
-                },
-                Some(Token::Comma) => {
-                    self.token_stream.next();
+                Some(Token::UndefinedValue) if self.options.lenient => Some(self.consume_keyword()),
+                Some(Token::LBrace) => {
+                    let frame = Frame::Object {
+                        property_assignments: Vec::new(),
+                        property_name: None,
+                        seen_keys: Vec::new(),
+                    };
+                    match self.push_frame(stack, frame) {
+                        Ok(()) => None,
+                        Err(error) => {
+                            skipped = true;
+                            self.resync(error, stack)?
+                        }
+                    }
+                }
+                Some(Token::LBracket) => {
+                    let frame = Frame::Array {
+                        elements: Vec::new(),
+                    };
+                    match self.push_frame(stack, frame) {
+                        Ok(()) => None,
+                        Err(error) => {
+                            skipped = true;
+                            self.resync(error, stack)?
+                        }
+                    }
+                }
+                _ => {
+                    let error = self.syntax_error("Unexpected token of input");
+                    skipped = true;
+                    self.resync(error, stack)?
+                }
+            };
+
+            // Attach finished values to their parent and close containers until
+            // another value has to be read.
+            let mut after_comma = false;
+            loop {
+                // input skipped by `resync` stands in for a value
+                let after_value = completed.is_some() || skipped;
+                if skipped {
+                    skipped = false;
+                    after_comma = false;
+                }
+                if let Some(value) = completed.take() {
+                    self.close_span();
+                    match stack.last_mut() {
+                        None => return Ok(value),
+                        // children were discarded too, so this drop is shallow
+                        Some(_) if self.discard_values => {}
+                        Some(Frame::Object {
+                            property_assignments,
+                            property_name,
+                            ..
+                        }) => {
+                            let property_name = property_name.take().unwrap_or_default();
+                            property_assignments.push(Node::new(
+                                SyntaxKind::PropertyAssignment,
+                                vec![
+                                    Node::new(SyntaxKind::Identifier(property_name), vec![]),
+                                    value,
+                                ],
+                            ));
+                        }
+                        Some(Frame::Array { elements }) => elements.push(value),
+                    }
+                }
+
+                match stack.last_mut() {
+                    Some(Frame::Object {
+                        property_name,
+                        seen_keys,
+                        ..
+                    }) => match self.token_stream.peek() {
+                        Some(Token::RBrace)
+                            if !after_comma || self.options.allow_trailing_commas =>
+                        {
+                            self.next_token();
+                            completed = stack.pop().map(Frame::into_node);
+                            after_comma = false;
+                        }
+                        Some(Token::RBrace) => {
+                            let error = self.syntax_error("Trailing comma");
+                            completed = self.resync(error, stack)?;
+                            skipped = true;
+                        }
+                        Some(Token::Comma) if after_value => {
+                            self.next_token();
+                            after_comma = true;
+                        }
+                        _ if after_value => {
+                            let error = self.syntax_error("Expected `,` or `}`");
+                            completed = self.resync(error, stack)?;
+                            skipped = true;
+                        }
+                        _ => match self.consume_property_name() {
+                            Ok((name, converted)) => {
+                                if !self.discard_values {
+                                    self.check_duplicate_key(seen_keys, &name, converted);
+                                }
+                                *property_name = Some(name);
+                                break;
+                            }
+                            Err(error) => {
+                                completed = self.resync(error, stack)?;
+                                skipped = true;
+                            }
+                        },
+                    },
+                    Some(Frame::Array { .. }) => match self.token_stream.peek() {
+                        Some(Token::RBracket)
+                            if !after_comma || self.options.allow_trailing_commas =>
+                        {
+                            self.next_token();
+                            completed = stack.pop().map(Frame::into_node);
+                            after_comma = false;
+                        }
+                        Some(Token::RBracket) => {
+                            let error = self.syntax_error("Trailing comma");
+                            completed = self.resync(error, stack)?;
+                            skipped = true;
+                        }
+                        Some(Token::Comma) if after_value => {
+                            self.next_token();
+                            after_comma = true;
+                        }
+                        _ if after_value => {
+                            let error = self.syntax_error("Expected `,` or `]`");
+                            completed = self.resync(error, stack)?;
+                            skipped = true;
+                        }
+                        _ => break,
+                    },
+                    None => unreachable!("a container is open while its contents are read"),
                 }
-                _ => return Err("Unexpected token of input".to_string()),
             }
         }
-        Ok(Node::new(
-            SyntaxKind::ObjectLiteralExpression,
-            property_assignments,
-        ))
     }
 
-    fn consume_array(&mut self) -> Result<Node, String> {
-        let mut elements = Vec::new();
-        self.token_stream.next();
+    /// Under `parse_recover`, records `error` and skips to the next `,` or
+    /// closing bracket of the innermost open container. At a `,` the caller
+    /// carries on as if a value had been read; otherwise the container is
+    /// closed and returned, consuming its bracket only if it is the right one.
+    /// Outside of `parse_recover`, or with no container open, `error` is
+    /// returned instead.
+    fn resync(
+        &mut self,
+        error: ParseError,
+        stack: &mut Vec<Frame>,
+    ) -> Result<Option<Node>, ParseError> {
+        if self.errors.is_none() || stack.is_empty() {
+            return Err(error);
+        }
+        self.record_error(error);
+        let mut depth = 0;
         loop {
             match self.token_stream.peek() {
-                Some(Token::RBracket) => {
-                    self.token_stream.next();
-                    break;
-                }
-                Some(Token::Comma) => {
-                    self.token_stream.next();
-                }
-                _ => match self.consume_value() {
-                    Ok(value) => elements.push(value),
-                    Err(e) => return Err(e),
-                },
+                Some(Token::LBrace) | Some(Token::LBracket) => depth += 1,
+                Some(Token::RBrace) | Some(Token::RBracket) if depth > 0 => depth -= 1,
+                Some(Token::Comma) if depth == 0 => return Ok(None),
+                Some(Token::RBrace) | Some(Token::RBracket) | Some(Token::End) | None => break,
+                _ => {}
+            }
+            self.next_token();
+        }
+        let closes = matches!(
+            (self.token_stream.peek(), stack.last()),
+            (Some(Token::RBrace), Some(Frame::Object { .. }))
+                | (Some(Token::RBracket), Some(Frame::Array { .. }))
+        );
+        if closes {
+            self.next_token();
+        }
+        Ok(stack.pop().map(Frame::into_node))
+    }
+
+    /// Adds an error for `parse_recover`, reporting a lex error that ended the
+    /// token stream in its place. Only the first error at a token is kept, as
+    /// the end of input may close several containers.
+    fn record_error(&mut self, error: ParseError) {
+        let error = self.check_lex_error::<()>(Err(error)).unwrap_err();
+        if let Some(errors) = &mut self.errors {
+            if errors.last().map(|last| last.index) != Some(error.index) {
+                errors.push(error);
             }
         }
-        Ok(Node::new(SyntaxKind::ArrayLiteralExpression, elements))
     }
 
-    fn consume_value(&mut self) -> Result<Node, String> {
+    fn consume_end(&mut self) -> Result<(), ParseError> {
         match self.token_stream.peek() {
-            Some(Token::StringValue(_)) => Ok(self.consume_string()),
-            Some(Token::NumberValue(_)) => Ok(self.consume_number()),
-            Some(Token::BooleanValue(_)) | Some(Token::NullValue) => Ok(self.consume_keyword()),
-            Some(Token::LBrace) => self.consume_object(),
-            Some(Token::LBracket) => self.consume_array(),
-            _ => Err("Unexpected token of input".to_string()),
+            Some(Token::End) => {
+                self.next_token();
+                Ok(())
+            }
+            _ => Err(self.syntax_error("Unexpected token after value")),
         }
     }
 
+    /// Parses the whole input as one document.
+    ///
+    /// # Panics
+    ///
+    /// Panics on any lexing or parsing error; use `try_parse` to handle them.
     pub fn parse(&mut self) -> Node {
-        let first_token = self.token_stream.peek();
-        let result = match first_token {
-            Some(Token::LBrace) => self.consume_object(),
-            Some(Token::LBracket) => self.consume_array(),
-            _ => Err("Unexpected the first token of input".to_string()),
-        };
-        match result {
+        match self.try_parse() {
             Ok(value) => value,
             Err(e) => panic!("{}", e),
         }
     }
+
+    pub fn try_parse(&mut self) -> Result<Node, ParseError> {
+        let result = self.consume_document();
+        self.check_lex_error(result)
+    }
+
+    /// Checks the input as `try_parse` does without building the tree:
+    /// values are dropped as soon as they are read and keys are not kept to
+    /// warn about duplicates, so memory grows only with nesting depth.
+    pub(crate) fn validate(&mut self) -> Result<(), ParseError> {
+        self.discard_values = true;
+        self.try_parse().map(drop)
+    }
+
+    /// Parses the whole input, recovering from syntax errors instead of
+    /// stopping at the first one: the parser skips to the next `,` or closing
+    /// bracket and carries on, dropping what it skipped. Returns the tree, if
+    /// the root value could be read at all, with every error found.
+    pub fn parse_recover(&mut self) -> (Option<Node>, Vec<ParseError>) {
+        self.errors = Some(Vec::new());
+        let result = match self.token_stream.peek() {
+            Some(Token::End) | None => Err(self.syntax_error("Unexpected end of input")),
+            _ => self.consume_value(),
+        };
+        let node = match result {
+            Ok(node) => {
+                if let Err(error) = self.consume_end() {
+                    self.record_error(error);
+                }
+                Some(node)
+            }
+            Err(error) => {
+                self.record_error(error);
+                None
+            }
+        };
+        if let Err(error) = self.check_lex_error(Ok(())) {
+            self.record_error(error);
+        }
+        (node, self.errors.take().unwrap_or_default())
+    }
+
+    /// Parses consecutive root values, such as newline-delimited JSON, until
+    /// the end of input. A value that fails to parse is yielded as an error
+    /// and reading resumes on the next line, so the values after it are kept.
+    pub fn parse_stream(&mut self) -> impl Iterator<Item = Result<Node, ParseError>> + use<'_, 'a> {
+        std::iter::from_fn(move || self.next_stream_value())
+    }
+
+    fn next_stream_value(&mut self) -> Option<Result<Node, ParseError>> {
+        if let Some(Token::End) | None = self.token_stream.peek() {
+            self.token_stream.lex_error.as_ref()?;
+        }
+        let start = self.token_stream.position;
+        let result = self.consume_value();
+        let result = self.check_lex_error(result);
+        if result.is_err() {
+            let stream = &mut self.token_stream;
+            // a value that stops short is followed by the next line's value
+            let next_line_started = stream.lex_error.is_none()
+                && stream.position > start
+                && stream.tokens.after_line_break();
+            if !next_line_started {
+                stream.skip_line();
+            }
+        }
+        Some(result)
+    }
+
+    /// Reports a lex error that ended the token stream in place of whatever
+    /// the parser made of that early end.
+    fn check_lex_error<T>(&self, result: Result<T, ParseError>) -> Result<T, ParseError> {
+        match &self.token_stream.lex_error {
+            Some((e, index)) => Err(ParseError {
+                kind: ParseErrorKind::Lex,
+                message: e.to_string(),
+                index: *index,
+                offset: e.offset(),
+            }),
+            None => result,
+        }
+    }
+
+    fn consume_document(&mut self) -> Result<Node, ParseError> {
+        let value = match self.token_stream.peek() {
+            Some(Token::End) | None => Err(self.syntax_error("Unexpected end of input")),
+            _ => self.consume_value(),
+        }?;
+        if let Err(error) = self.consume_end() {
+            value.drop_deep();
+            return Err(error);
+        }
+        Ok(value)
+    }
+}
+
+/// Attaches each comment to a value: to the one ending earlier on its line,
+/// else to the next member of the same object or array, else to the
+/// container itself.
+fn attach_comments(input: &str, spans: &[Span], comments: &[Range<usize>]) -> Vec<Comment> {
+    if spans.is_empty() {
+        return vec![];
+    }
+    // spans come in document order, so each one's parent is the closest
+    // earlier span containing it
+    let contains = |outer: &Range<usize>, inner: &Range<usize>| {
+        outer.start <= inner.start && inner.end <= outer.end
+    };
+    let mut parents: Vec<Option<usize>> = Vec::with_capacity(spans.len());
+    let mut children: Vec<Vec<usize>> = vec![Vec::new(); spans.len()];
+    let mut open: Vec<usize> = Vec::new();
+    for (index, span) in spans.iter().enumerate() {
+        while let Some(&top) = open.last() {
+            if contains(&spans[top].range, &span.range) {
+                break;
+            }
+            open.pop();
+        }
+        parents.push(open.last().copied());
+        if let Some(&parent) = open.last() {
+            children[parent].push(index);
+        }
+        open.push(index);
+    }
+
+    // Whether only spaces, commas and other comments separate `from` from
+    // the comment at `comments[index]`.
+    let same_line = |from: usize, index: usize| {
+        let mut position = from;
+        for range in comments[..index].iter().filter(|range| range.start >= from) {
+            if !is_line_filler(&input[position..range.start]) {
+                return false;
+            }
+            position = range.end;
+        }
+        is_line_filler(&input[position..comments[index].start])
+    };
+    let comment = |index: usize, span: usize, placement| Comment {
+        text: input[comments[index].clone()].to_string(),
+        pointer: spans[span].pointer.clone(),
+        placement,
+    };
+
+    let mut attached = Vec::with_capacity(comments.len());
+    for (index, range) in comments.iter().enumerate() {
+        let started = spans.partition_point(|span| span.range.start < range.start);
+        if started == 0 {
+            attached.push(comment(index, 0, CommentPlacement::Above));
+            continue;
+        }
+        // walk up to the innermost container around the comment, keeping the
+        // child of it that ends before the comment
+        let mut previous = None;
+        let mut current = Some(started - 1);
+        while let Some(span) = current {
+            if contains(&spans[span].range, range) {
+                break;
+            }
+            previous = Some(span);
+            current = parents[span];
+        }
+        if let Some(previous) = previous {
+            if same_line(spans[previous].range.end, index) {
+                attached.push(comment(index, previous, CommentPlacement::EndOfLine));
+                continue;
+            }
+        }
+        let Some(container) = current else {
+            attached.push(comment(index, 0, CommentPlacement::Below));
+            continue;
+        };
+        let siblings = &children[container];
+        let next = match previous {
+            Some(previous) => siblings.iter().skip_while(|&&span| span != previous).nth(1),
+            None => siblings.first(),
+        };
+        attached.push(match next {
+            Some(&next) => comment(index, next, CommentPlacement::Above),
+            None => comment(index, container, CommentPlacement::Inside),
+        });
+    }
+    attached
+}
+
+fn is_line_filler(text: &str) -> bool {
+    text.chars()
+        .all(|c| c == ',' || (c.is_whitespace() && c != '\n'))
+}
+
+/// Parses the first JSON value in `input` and returns it together with the
+/// unconsumed remainder, so several values can be read one after another.
+/// Error offsets are byte offsets into `input`.
+pub fn parse_one(input: &str) -> Result<(Node, &str), ParseError> {
+    // input with no complete value is parsed whole to say where it falls short
+    let end = value_end(input).unwrap_or(input.len());
+    let (value, rest) = input.split_at(end);
+    let node = Parser::new(value).try_parse()?;
+    Ok((node, rest))
+}
+
+/// Parses `input` as strict JSON straight into a `Value`. Every malformed
+/// input, including one nested past the default `max_depth`, is an error
+/// rather than a panic.
+pub fn parse_str(input: &str) -> Result<Value, ParseError> {
+    Ok(Parser::new(input).try_parse()?.to_value())
+}
+
+/// Finds the byte offset just past the first value in `input` without
+/// tokenizing the rest of it.
+fn value_end(input: &str) -> Option<usize> {
+    let start = input.len() - input.trim_start().len();
+    let mut chars = input[start..].char_indices().peekable();
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    while let Some((index, c)) = chars.next() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => {
+                    in_string = false;
+                    if depth == 0 {
+                        return Some(start + index + 1);
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '{' | '[' => depth += 1,
+            '}' | ']' if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(start + index + 1);
+                }
+            }
+            _ if depth == 0 => {
+                let at_delimiter = match chars.peek() {
+                    Some((_, next)) => {
+                        next.is_whitespace()
+                            || matches!(next, ',' | ':' | '{' | '}' | '[' | ']' | '"')
+                    }
+                    None => true,
+                };
+                if at_delimiter {
+                    return Some(start + index + c.len_utf8());
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Parses `input` like `Parser::try_parse`, additionally measuring the time
+/// spent in the lexer and in the parser. Plain `parse` calls pay no timing
+/// overhead.
+pub fn parse_with_timings(input: &str) -> Result<(Node, ParseTimings), ParseError> {
+    let start = Instant::now();
+    let tokens = Lexer::new(input).tokenize();
+    let lex = start.elapsed();
+
+    let start = Instant::now();
+    let node = tokens.ok().and_then(|tokens| {
+        Parser::from_tokens(tokens, ParseOptions::default())
+            .try_parse()
+            .ok()
+    });
+    let parse = start.elapsed();
+
+    let node = match node {
+        Some(node) => node,
+        // pre-lexed tokens carry no offsets, so find the error again in the
+        // text itself
+        None => Parser::new(input).try_parse()?,
+    };
+    Ok((node, ParseTimings { lex, parse }))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::node::Number;
 
     #[test]
     fn test_consume_string() {
@@ -151,7 +1093,7 @@ mod tests {
     fn test_consume_number() {
         let mut parser = Parser::new("123");
         let number = parser.consume_number();
-        assert_eq!(number.kind, SyntaxKind::NumberLiteral(123.0));
+        assert_eq!(number.kind, SyntaxKind::NumberLiteral(123.0.into()));
     }
 
     #[test]
@@ -169,24 +1111,123 @@ mod tests {
     }
 
     #[test]
-    fn test_consume_property_assignment() {
-        let success_cases = vec![
+    fn test_consume_property_name() {
+        let cases = vec![
+            (r#""hello": 123"#, Ok(("hello".to_string(), false))),
             (
-                r#""hello": 123"#,
-                Ok(Node::new(
-                    SyntaxKind::PropertyAssignment,
-                    vec![
-                        Node::new(SyntaxKind::Identifier("hello".to_string()), vec![]),
-                        Node::new(SyntaxKind::NumberLiteral(123.0), vec![]),
-                    ],
-                )),
+                r#"123: "hello""#,
+                Err("Unexpected token of input".to_string()),
             ),
-            (r#"123: "hello""#, Err("Unexpected Identifier".to_string())),
+            (r#""hello" 123"#, Err("Expected `:`".to_string())),
         ];
 
-        for (input, expected) in success_cases {
+        for (input, expected) in cases {
             let mut parser = Parser::new(input);
-            assert_eq!(parser.consume_property_assignment(), expected);
+            assert_eq!(
+                parser.consume_property_name().map_err(|e| e.message),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_allow_non_finite() {
+        let options = ParseOptions {
+            allow_non_finite: true,
+            ..Default::default()
+        };
+        let node = Parser::with_options("[Infinity, -Infinity, NaN, 1]", options).parse();
+        let lexemes: Vec<_> = node
+            .children
+            .iter()
+            .map(|child| match &child.kind {
+                SyntaxKind::NumberLiteral(number) => number.lexeme.as_str(),
+                kind => panic!("{:?}", kind),
+            })
+            .collect();
+        assert_eq!(lexemes, vec!["Infinity", "-Infinity", "NaN", "1"]);
+
+        let cases = vec![
+            ("[Infinity]", "Unexpected keyword: Infinity at byte 1"),
+            ("[-Infinity]", "Unexpected number: - at byte 1"),
+            ("[NaN]", "Unexpected keyword: NaN at byte 1"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(
+                parse_one(input).map_err(|e| e.message),
+                Err(expected.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn test_allow_single_quotes() {
+        let options = ParseOptions {
+            allow_single_quotes: true,
+            ..Default::default()
+        };
+        let cases = vec![
+            (r#"{"a": 'b'}"#, Ok(r#"{"a": "b"}"#)),
+            (r#"{'a': "b"}"#, Ok(r#"{"a": "b"}"#)),
+            (r#"['it\'s', "it's"]"#, Ok(r#"["it's", "it's"]"#)),
+            (r#"{'a": 1}"#, Err("Unexpected end of input at byte 8")),
+            (r#"{"a': 1}"#, Err("Unexpected end of input at byte 8")),
+        ];
+
+        for (input, expected) in cases {
+            let expected = expected
+                .map(|expected| Parser::new(expected).parse())
+                .map_err(|message| message.to_string());
+            let actual = Parser::with_options(input, options)
+                .try_parse()
+                .map_err(|e| e.message);
+            assert_eq!(actual, expected);
+        }
+
+        assert_eq!(
+            parse_one(r#"{"a": 'b'}"#).map_err(|e| e.message),
+            Err("Unexpected character: ' at byte 6".to_string())
+        );
+    }
+
+    #[test]
+    fn test_allow_unquoted_keys() {
+        let options = ParseOptions {
+            allow_unquoted_keys: true,
+            ..Default::default()
+        };
+        let cases = vec![
+            (r#"{foo: 1}"#, Ok(r#"{"foo": 1}"#)),
+            (
+                r#"{_a: {$b: [1]}, "c": 2}"#,
+                Ok(r#"{"_a": {"$b": [1]}, "c": 2}"#),
+            ),
+            (
+                r#"{true: 1, null: 2, undefined: 3}"#,
+                Ok(r#"{"true": 1, "null": 2, "undefined": 3}"#),
+            ),
+            (r#"{foo: bar}"#, Err("Unexpected token of input")),
+            (r#"[foo]"#, Err("Unexpected token of input")),
+        ];
+
+        for (input, expected) in cases {
+            let expected = expected
+                .map(|expected| Parser::new(expected).parse())
+                .map_err(|message| message.to_string());
+            let mut parser = Parser::with_options(input, options);
+            assert_eq!(parser.try_parse().map_err(|e| e.message), expected);
+            assert!(parser.warnings().is_empty());
+        }
+
+        let cases = vec![
+            (r#"{foo: 1}"#, "Unexpected keyword: foo at byte 1"),
+            (r#"{true: 1}"#, "Unexpected token of input"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(
+                parse_one(input).map_err(|e| e.message),
+                Err(expected.to_string())
+            );
         }
     }
 
@@ -201,7 +1242,7 @@ mod tests {
                         SyntaxKind::PropertyAssignment,
                         vec![
                             Node::new(SyntaxKind::Identifier("hello".to_string()), vec![]),
-                            Node::new(SyntaxKind::NumberLiteral(123.0), vec![]),
+                            Node::new(SyntaxKind::NumberLiteral(123.0.into()), vec![]),
                         ],
                     )],
                 ),
@@ -215,7 +1256,7 @@ mod tests {
                             SyntaxKind::PropertyAssignment,
                             vec![
                                 Node::new(SyntaxKind::Identifier("hello".to_string()), vec![]),
-                                Node::new(SyntaxKind::NumberLiteral(123.0), vec![]),
+                                Node::new(SyntaxKind::NumberLiteral(123.0.into()), vec![]),
                             ],
                         ),
                         Node::new(
@@ -243,7 +1284,7 @@ mod tests {
                 r#"[123]"#,
                 Node::new(
                     SyntaxKind::ArrayLiteralExpression,
-                    vec![Node::new(SyntaxKind::NumberLiteral(123.0), vec![])],
+                    vec![Node::new(SyntaxKind::NumberLiteral(123.0.into()), vec![])],
                 ),
             ),
             (
@@ -251,7 +1292,7 @@ mod tests {
                 Node::new(
                     SyntaxKind::ArrayLiteralExpression,
                     vec![
-                        Node::new(SyntaxKind::NumberLiteral(123.0), vec![]),
+                        Node::new(SyntaxKind::NumberLiteral(123.0.into()), vec![]),
                         Node::new(SyntaxKind::StringLiteral("hello".to_string()), vec![]),
                     ],
                 ),
@@ -269,7 +1310,7 @@ mod tests {
         let cases = vec![
             (
                 "123",
-                Ok(Node::new(SyntaxKind::NumberLiteral(123.0), vec![])),
+                Ok(Node::new(SyntaxKind::NumberLiteral(123.0.into()), vec![])),
             ),
             (
                 r#""hello""#,
@@ -289,7 +1330,7 @@ mod tests {
                         SyntaxKind::PropertyAssignment,
                         vec![
                             Node::new(SyntaxKind::Identifier("hello".to_string()), vec![]),
-                            Node::new(SyntaxKind::NumberLiteral(123.0), vec![]),
+                            Node::new(SyntaxKind::NumberLiteral(123.0.into()), vec![]),
                         ],
                     )],
                 )),
@@ -299,22 +1340,19 @@ mod tests {
                 Ok(Node::new(
                     SyntaxKind::ArrayLiteralExpression,
                     vec![
-                        Node::new(SyntaxKind::NumberLiteral(1.0), vec![]),
-                        Node::new(SyntaxKind::NumberLiteral(2.0), vec![]),
-                        Node::new(SyntaxKind::NumberLiteral(3.0), vec![]),
+                        Node::new(SyntaxKind::NumberLiteral(1.0.into()), vec![]),
+                        Node::new(SyntaxKind::NumberLiteral(2.0.into()), vec![]),
+                        Node::new(SyntaxKind::NumberLiteral(3.0.into()), vec![]),
                     ],
                 )),
             ),
             ("", Err("Unexpected token of input".to_string())),
-            (
-                r#"{"hello": 123"#,
-                Err("Unexpected token of input".to_string()),
-            ),
+            (r#"{"hello": 123"#, Err("Expected `,` or `}`".to_string())),
         ];
 
         for (input, expected) in cases {
             let mut parser = Parser::new(input);
-            assert_eq!(parser.consume_value(), expected);
+            assert_eq!(parser.consume_value().map_err(|e| e.message), expected);
         }
     }
 
@@ -329,7 +1367,7 @@ mod tests {
                         SyntaxKind::PropertyAssignment,
                         vec![
                             Node::new(SyntaxKind::Identifier("hello".to_string()), vec![]),
-                            Node::new(SyntaxKind::NumberLiteral(123.0), vec![]),
+                            Node::new(SyntaxKind::NumberLiteral(123.0.into()), vec![]),
                         ],
                     )],
                 ),
@@ -339,9 +1377,9 @@ mod tests {
                 Node::new(
                     SyntaxKind::ArrayLiteralExpression,
                     vec![
-                        Node::new(SyntaxKind::NumberLiteral(1.0), vec![]),
-                        Node::new(SyntaxKind::NumberLiteral(2.0), vec![]),
-                        Node::new(SyntaxKind::NumberLiteral(3.0), vec![]),
+                        Node::new(SyntaxKind::NumberLiteral(1.0.into()), vec![]),
+                        Node::new(SyntaxKind::NumberLiteral(2.0.into()), vec![]),
+                        Node::new(SyntaxKind::NumberLiteral(3.0.into()), vec![]),
                     ],
                 ),
             ),
@@ -356,9 +1394,9 @@ mod tests {
                             Node::new(
                                 SyntaxKind::ArrayLiteralExpression,
                                 vec![
-                                    Node::new(SyntaxKind::NumberLiteral(1.0), vec![]),
-                                    Node::new(SyntaxKind::NumberLiteral(2.0), vec![]),
-                                    Node::new(SyntaxKind::NumberLiteral(3.0), vec![]),
+                                    Node::new(SyntaxKind::NumberLiteral(1.0.into()), vec![]),
+                                    Node::new(SyntaxKind::NumberLiteral(2.0.into()), vec![]),
+                                    Node::new(SyntaxKind::NumberLiteral(3.0.into()), vec![]),
                                 ],
                             ),
                         ],
@@ -372,4 +1410,726 @@ mod tests {
             assert_eq!(parser.parse(), expected);
         }
     }
+
+    #[test]
+    fn test_parse_with_timings() {
+        let input = r#"{"hello": [1, 2, 3], "world": {"foo": "bar"}}"#;
+        let (node, timings) = parse_with_timings(input).unwrap();
+        assert_eq!(node, Parser::new(input).parse());
+        assert!(timings.total() >= timings.lex);
+        assert!(timings.total() >= timings.parse);
+        assert_eq!(timings.total(), timings.lex + timings.parse);
+
+        let cases = vec![
+            ("[1, @]", ParseErrorKind::Lex, 4),
+            ("[1 2]", ParseErrorKind::Syntax, 3),
+            ("[1 2 @]", ParseErrorKind::Syntax, 3),
+            (r#"{"a": "#, ParseErrorKind::Syntax, 6),
+        ];
+        for (input, kind, offset) in cases {
+            let error = parse_with_timings(input).unwrap_err();
+            assert_eq!((error.kind, error.offset), (kind, offset), "{}", input);
+            assert_eq!(error, Parser::new(input).try_parse().unwrap_err());
+        }
+    }
+
+    #[test]
+    fn test_parse_deeply_nested() {
+        let depth = 50_000;
+        let options = ParseOptions {
+            max_depth: usize::MAX,
+            ..Default::default()
+        };
+        let input = format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        let node = Parser::with_options(&input, options).parse();
+
+        let mut current = &node;
+        let mut nested = 1;
+        while let Some(child) = current.children.first() {
+            current = child;
+            nested += 1;
+        }
+        assert_eq!(nested, depth);
+        node.drop_deep();
+
+        let input = format!("{}1{}", r#"{"a": ["#.repeat(depth), "]}".repeat(depth));
+        let node = Parser::with_options(&input, options).parse();
+        assert_eq!(node.kind, SyntaxKind::ObjectLiteralExpression);
+        node.drop_deep();
+
+        // values already read are released when a later token fails
+        let nested = format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        let cases = vec![
+            (
+                format!("[{}, @]", nested),
+                "Unexpected character: @ at byte 100003",
+            ),
+            (format!("[{}, }}", nested), "Unexpected token of input"),
+            (format!("{} 1", nested), "Unexpected token after value"),
+        ];
+        for (input, expected) in cases {
+            let actual = Parser::with_options(&input, options).try_parse();
+            assert_eq!(actual.map_err(|e| e.message), Err(expected.to_string()));
+        }
+    }
+
+    #[test]
+    fn test_lenient_undefined() {
+        let input = r#"{"a": undefined, "b": [undefined]}"#;
+        let mut parser = Parser::with_options(
+            input,
+            ParseOptions {
+                lenient: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            parser.parse(),
+            Node::new(
+                SyntaxKind::ObjectLiteralExpression,
+                vec![
+                    Node::new(
+                        SyntaxKind::PropertyAssignment,
+                        vec![
+                            Node::new(SyntaxKind::Identifier("a".to_string()), vec![]),
+                            Node::new(SyntaxKind::NullKeyword, vec![]),
+                        ],
+                    ),
+                    Node::new(
+                        SyntaxKind::PropertyAssignment,
+                        vec![
+                            Node::new(SyntaxKind::Identifier("b".to_string()), vec![]),
+                            Node::new(
+                                SyntaxKind::ArrayLiteralExpression,
+                                vec![Node::new(SyntaxKind::NullKeyword, vec![])],
+                            ),
+                        ],
+                    ),
+                ],
+            )
+        );
+        assert_eq!(parser.warnings().len(), 2);
+
+        let mut parser = Parser::new(input);
+        assert_eq!(
+            parser.consume_value().map_err(|e| e.message),
+            Err("Unexpected token of input".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_one() {
+        let (node, rest) = parse_one(r#"{"a": [1, "]"]} [2] true"#).unwrap();
+        assert_eq!(node, Parser::new(r#"{"a": [1, "]"]}"#).parse());
+        assert_eq!(rest, " [2] true");
+
+        let (node, rest) = parse_one(rest).unwrap();
+        assert_eq!(node, Parser::new("[2]").parse());
+        assert_eq!(rest, " true");
+
+        let (node, rest) = parse_one(rest).unwrap();
+        assert_eq!(node, Node::new(SyntaxKind::TrueKeyword, vec![]));
+        assert_eq!(rest, "");
+
+        let (node, rest) = parse_one("\"hi\"\n42\n").unwrap();
+        assert_eq!(
+            node,
+            Node::new(SyntaxKind::StringLiteral("hi".to_string()), vec![])
+        );
+        assert_eq!(rest, "\n42\n");
+
+        let cases = vec![
+            ("   ", "Unexpected end of input", 0, 3),
+            (r#"{"a": 1"#, "Expected `,` or `}`", 4, 7),
+            ("  [1, @]", "Unexpected character: @ at byte 6", 3, 6),
+        ];
+        for (input, message, index, offset) in cases {
+            let error = parse_one(input).unwrap_err();
+            assert_eq!(
+                (error.message.as_str(), error.index, error.offset),
+                (message, index, offset)
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_str() {
+        assert_eq!(
+            parse_str(r#"{"name": "x", "tags": [1, true, null], "nested": {}}"#),
+            Ok(Value::Object(vec![
+                ("name".to_string(), Value::String("x".to_string())),
+                (
+                    "tags".to_string(),
+                    Value::Array(vec![Value::Number(1.0), Value::Bool(true), Value::Null]),
+                ),
+                ("nested".to_string(), Value::Object(vec![])),
+            ]))
+        );
+
+        let deep = "[".repeat(1000);
+        let cases = vec![
+            ("", "Unexpected end of input"),
+            ("{", "Unexpected token of input"),
+            ("[1,]", "Trailing comma"),
+            (r#"{"a" 1}"#, "Expected `:`"),
+            ("1 2", "Unexpected token after value"),
+            ("@", "Unexpected character: @ at byte 0"),
+            (r#""\x""#, "Invalid escape sequence: \\x at byte 0"),
+            (
+                "[\"a\nb\"]",
+                "Unescaped control character: U+000A at byte 1",
+            ),
+            ("nul", "Unexpected keyword: nul at byte 0"),
+            ("\"open", "Unexpected end of input at byte 5"),
+            (deep.as_str(), "Maximum nesting depth of 128 exceeded"),
+        ];
+
+        for (input, expected) in cases {
+            let err = parse_str(input).unwrap_err();
+            assert_eq!(err.message, expected, "{:?}", input);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Unexpected token after value")]
+    fn test_parse_trailing_tokens() {
+        Parser::new(r#"{"a": 1} [2]"#).parse();
+    }
+
+    #[test]
+    fn test_try_parse() {
+        use ParseErrorKind::*;
+
+        let cases = vec![
+            (r#"{"a": [1, 2]}"#, Ok(r#"{"a": [1, 2]}"#)),
+            (
+                r#"{"a": 1} [2]"#,
+                Err((Syntax, "Unexpected token after value", 5, 9)),
+            ),
+            (
+                r#"{"a": 1, 2: 3}"#,
+                Err((Syntax, "Unexpected token of input", 5, 9)),
+            ),
+            (r#"[1, }"#, Err((Syntax, "Unexpected token of input", 3, 4))),
+            (r#"[1, 2"#, Err((Syntax, "Expected `,` or `]`", 4, 5))),
+            (r#"[1 2]"#, Err((Syntax, "Expected `,` or `]`", 2, 3))),
+            (
+                r#"{"a": 1 "b": 2}"#,
+                Err((Syntax, "Expected `,` or `}`", 4, 8)),
+            ),
+            (r#"{"a" 1}"#, Err((Syntax, "Expected `:`", 2, 5))),
+            (r#"[1, 2,]"#, Err((Syntax, "Trailing comma", 5, 6))),
+            (r#"{"a": 1,}"#, Err((Syntax, "Trailing comma", 5, 8))),
+            (
+                r#"[1,, 2]"#,
+                Err((Syntax, "Unexpected token of input", 3, 3)),
+            ),
+            (
+                r#"{"a": 1,, "b": 2}"#,
+                Err((Syntax, "Unexpected token of input", 5, 8)),
+            ),
+            (r#"[,]"#, Err((Syntax, "Unexpected token of input", 1, 1))),
+            ("", Err((Syntax, "Unexpected end of input", 0, 0))),
+            (":", Err((Syntax, "Unexpected token of input", 0, 0))),
+            ("1 2", Err((Syntax, "Unexpected token after value", 1, 2))),
+            (
+                r#""a" ,"#,
+                Err((Syntax, "Unexpected token after value", 1, 4)),
+            ),
+            (
+                r#"[true, @]"#,
+                Err((Lex, "Unexpected character: @ at byte 7", 3, 7)),
+            ),
+        ];
+
+        for (input, expected) in cases {
+            let expected = expected
+                .map(|expected| Parser::new(expected).parse())
+                .map_err(|(kind, message, index, offset)| ParseError {
+                    kind,
+                    message: message.to_string(),
+                    index,
+                    offset,
+                });
+            assert_eq!(Parser::new(input).try_parse(), expected);
+        }
+    }
+
+    #[test]
+    fn test_render_error() {
+        let deep = "[\n".repeat(10);
+        let cases = vec![
+            (
+                "{\n    \"a\" 1\n}",
+                "error: Expected `:`\n --> 2:9\n  |\n2 |     \"a\" 1\n  |         ^",
+            ),
+            (
+                "[1, 2",
+                "error: Expected `,` or `]`\n --> 1:6\n  |\n1 | [1, 2\n  |      ^",
+            ),
+            (
+                "[\r\n\t1,\r\n\t@\r\n]\r\n",
+                "error: Unexpected character: @ at byte 9\n --> 3:2\n  |\n3 | \t@\n  | \t^",
+            ),
+            (
+                "[1,\n\n",
+                "error: Unexpected token of input\n --> 1:4\n  |\n1 | [1,\n  |    ^",
+            ),
+            (
+                deep.as_str(),
+                "error: Unexpected token of input\n  --> 10:2\n   |\n10 | [\n   |  ^",
+            ),
+        ];
+
+        for (input, expected) in cases {
+            let err = Parser::new(input).try_parse().unwrap_err();
+            assert_eq!(render_error(input, &err), expected, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_line_col() {
+        let cases = vec![
+            ("{\n    \"a\" 1\n}", (2, 9)),
+            ("[1, 2", (1, 6)),
+            ("[\"日本\", @]", (1, 8)),
+            ("[\r\n\t1,\r\n\t@\r\n]\r\n", (3, 2)),
+            ("[1,\n\n", (1, 4)),
+        ];
+
+        for (input, expected) in cases {
+            let err = Parser::new(input).try_parse().unwrap_err();
+            assert_eq!(err.line_col(input), expected, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let options = |max_depth| ParseOptions {
+            max_depth,
+            ..Default::default()
+        };
+        let cases = vec![
+            ("[[1]]", 2, Ok(2)),
+            (r#"{"a": [{}]}"#, 3, Ok(3)),
+            ("[[1]]", 1, Err((1, 1))),
+            (r#"{"a": [{"b": [[]]}]}"#, 4, Err((8, 14))),
+        ];
+
+        for (input, max_depth, expected) in cases {
+            let result = Parser::with_options(input, options(max_depth)).try_parse();
+            match expected {
+                Ok(_) => assert_eq!(result, Ok(Parser::new(input).parse())),
+                Err((index, offset)) => assert_eq!(
+                    result,
+                    Err(ParseError {
+                        kind: ParseErrorKind::DepthExceeded,
+                        message: format!("Maximum nesting depth of {} exceeded", max_depth),
+                        index,
+                        offset,
+                    })
+                ),
+            }
+        }
+
+        let deep = format!("{}{}", "[".repeat(129), "]".repeat(129));
+        let error = Parser::new(&deep).try_parse().unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::DepthExceeded);
+    }
+
+    #[test]
+    fn test_parse_scalar_document() {
+        let cases = vec![
+            ("true", SyntaxKind::TrueKeyword),
+            (" false ", SyntaxKind::FalseKeyword),
+            ("null", SyntaxKind::NullKeyword),
+            ("42", SyntaxKind::NumberLiteral(42.0.into())),
+            (
+                "-1.5e2",
+                SyntaxKind::NumberLiteral(Number {
+                    value: -150.0,
+                    lexeme: "-1.5e2".to_string(),
+                }),
+            ),
+            (r#""hi""#, SyntaxKind::StringLiteral("hi".to_string())),
+        ];
+
+        for (input, expected) in cases {
+            let node = Parser::new(input).parse();
+            assert_eq!(node, Node::new(expected, vec![]));
+        }
+    }
+
+    #[test]
+    fn test_lex_error() {
+        let mut parser = Parser::new(r#"{"a": tru}"#);
+        assert_eq!(parser.lex_error(), None);
+        assert!(parser.try_parse().is_err());
+        assert_eq!(
+            parser.lex_error(),
+            Some(&LexError::InvalidKeyword {
+                keyword: "tru".to_string(),
+                offset: 6,
+            })
+        );
+        let mut parser = Parser::new(r#"{"a": true}"#);
+        assert!(parser.try_parse().is_ok());
+        assert_eq!(parser.lex_error(), None);
+
+        // tokens are read lazily, so a syntax error before the bad token wins
+        let mut parser = Parser::new(r#"[1 2 @]"#);
+        assert_eq!(
+            parser.try_parse().map_err(|e| e.message),
+            Err("Expected `,` or `]`".to_string())
+        );
+        assert_eq!(parser.lex_error(), None);
+
+        assert_eq!(
+            parse_one("[1, @]").map_err(|e| e.message),
+            Err("Unexpected character: @ at byte 4".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_recover() {
+        let cases = vec![
+            (r#"{"a": 1}"#, Some(r#"{"a":1}"#), vec![]),
+            (
+                r#"{"a": , "b": 2, "c": [1 2], "d": 4}"#,
+                Some(r#"{"b":2,"c":[1],"d":4}"#),
+                vec![
+                    ("Unexpected token of input", 3),
+                    ("Expected `,` or `]`", 12),
+                ],
+            ),
+            (
+                r#"{"a": 1 "b": 2, "c": }"#,
+                Some(r#"{"a":1}"#),
+                vec![
+                    ("Expected `,` or `}`", 4),
+                    ("Unexpected token of input", 10),
+                ],
+            ),
+            (
+                r#"[1, [2, {"a": 3], 4,]"#,
+                Some(r#"[1,[2,{"a":3}],4]"#),
+                vec![("Expected `,` or `}`", 10), ("Trailing comma", 14)],
+            ),
+            (
+                r#"[[1, {"a": 2"#,
+                Some(r#"[[1,{"a":2}]]"#),
+                vec![("Expected `,` or `}`", 8)],
+            ),
+            (
+                r#"[1, @]"#,
+                Some("[1]"),
+                vec![("Unexpected character: @ at byte 4", 3)],
+            ),
+            (
+                r#"[1] 2"#,
+                Some("[1]"),
+                vec![("Unexpected token after value", 3)],
+            ),
+            (r#"}"#, None, vec![("Unexpected token of input", 0)]),
+            (r#""#, None, vec![("Unexpected end of input", 0)]),
+        ];
+
+        for (input, expected_node, expected_errors) in cases {
+            let (node, errors) = Parser::new(input).parse_recover();
+            assert_eq!(
+                node.map(|node| node.to_json()),
+                expected_node.map(String::from),
+                "{}",
+                input
+            );
+            let errors: Vec<(&str, usize)> = errors
+                .iter()
+                .map(|e| (e.message.as_str(), e.index))
+                .collect();
+            assert_eq!(errors, expected_errors, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_spans() {
+        let input = "{\"a\": [1, \"x\"],\n \"b/c\": {\"d\": null}, \"e\": []}";
+        let options = ParseOptions {
+            spans: true,
+            ..Default::default()
+        };
+        let mut parser = Parser::with_options(input, options);
+        parser.parse();
+        let spans: Vec<(&str, &str)> = parser
+            .spans()
+            .iter()
+            .map(|span| (span.pointer.as_str(), &input[span.range.clone()]))
+            .collect();
+        assert_eq!(
+            spans,
+            vec![
+                ("", input),
+                ("/a", "[1, \"x\"]"),
+                ("/a/0", "1"),
+                ("/a/1", "\"x\""),
+                ("/b~1c", "{\"d\": null}"),
+                ("/b~1c/d", "null"),
+                ("/e", "[]"),
+            ]
+        );
+
+        let mut parser = Parser::new(input);
+        parser.parse();
+        assert!(parser.spans().is_empty());
+
+        // each value of a stream starts over at the root
+        let mut parser = Parser::with_options(" 1\n[true]", options);
+        let mut stream = parser.parse_stream();
+        stream.next();
+        stream.next();
+        drop(stream);
+        assert_eq!(
+            parser.spans(),
+            &[
+                Span {
+                    pointer: String::new(),
+                    range: 3..9,
+                },
+                Span {
+                    pointer: "/0".to_string(),
+                    range: 4..8,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_comments() {
+        let input = r#"// header
+{
+    // about a
+    "a": [1, /* one */ 2], // the list
+    "b": /* before b */ {
+        "c": null
+        // end of b
+    }
+    // end of root
+} // after
+/* last */"#;
+        let options = ParseOptions {
+            allow_comments: true,
+            spans: true,
+            ..Default::default()
+        };
+        let mut parser = Parser::with_options(input, options);
+        parser.parse();
+        let comments = parser.comments();
+        let comments: Vec<(&str, &str, CommentPlacement)> = comments
+            .iter()
+            .map(|comment| {
+                (
+                    comment.text.as_str(),
+                    comment.pointer.as_str(),
+                    comment.placement,
+                )
+            })
+            .collect();
+        assert_eq!(
+            comments,
+            vec![
+                ("// header", "", CommentPlacement::Above),
+                ("// about a", "/a", CommentPlacement::Above),
+                ("/* one */", "/a/0", CommentPlacement::EndOfLine),
+                ("// the list", "/a", CommentPlacement::EndOfLine),
+                ("/* before b */", "/b", CommentPlacement::Above),
+                ("// end of b", "/b", CommentPlacement::Inside),
+                ("// end of root", "", CommentPlacement::Inside),
+                ("// after", "", CommentPlacement::EndOfLine),
+                ("/* last */", "", CommentPlacement::Below),
+            ]
+        );
+
+        let cases = vec![
+            ("[/* empty */]", "", CommentPlacement::Inside),
+            ("[1 // one\n, 2]", "/0", CommentPlacement::EndOfLine),
+            ("[1\n// two\n, 2]", "/1", CommentPlacement::Above),
+            ("[1, /* a */ /* b */\n2]", "/0", CommentPlacement::EndOfLine),
+        ];
+        for (input, pointer, placement) in cases {
+            let mut parser = Parser::with_options(input, options);
+            parser.parse();
+            let comment = parser.comments().pop().unwrap();
+            assert_eq!(
+                (comment.pointer.as_str(), comment.placement),
+                (pointer, placement),
+                "{}",
+                input
+            );
+        }
+
+        // comments are only attached with spans recorded
+        let mut parser = Parser::with_options(
+            input,
+            ParseOptions {
+                spans: false,
+                ..options
+            },
+        );
+        parser.parse();
+        assert!(parser.comments().is_empty());
+    }
+
+    #[test]
+    fn test_parse_stream() {
+        let cases = vec![
+            ("", vec![]),
+            ("1 2\n", vec![Ok("1"), Ok("2")]),
+            (
+                r#"{"a":1}{"b":[2]}"#,
+                vec![Ok(r#"{"a":1}"#), Ok(r#"{"b":[2]}"#)],
+            ),
+            (
+                "{\"a\": 1}\n{\"b\": tru}\n{\"c\": 3}\n",
+                vec![
+                    Ok(r#"{"a":1}"#),
+                    Err("Unexpected keyword: tru at byte 15"),
+                    Ok(r#"{"c":3}"#),
+                ],
+            ),
+            (
+                "[1, 2\n[3]\n}\n{\"a\" 1} 4\n5",
+                vec![
+                    Err("Expected `,` or `]`"),
+                    Ok("[3]"),
+                    Err("Unexpected token of input"),
+                    Err("Expected `:`"),
+                    Ok("5"),
+                ],
+            ),
+            ("[1,\n", vec![Err("Unexpected token of input")]),
+        ];
+
+        for (input, expected) in cases {
+            let mut parser = Parser::new(input);
+            let values: Vec<Result<String, String>> = parser
+                .parse_stream()
+                .map(|result| result.map(|node| node.to_json()).map_err(|e| e.message))
+                .collect();
+            let expected: Vec<Result<String, String>> = expected
+                .into_iter()
+                .map(|result| result.map(String::from).map_err(String::from))
+                .collect();
+            assert_eq!(values, expected, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_allow_trailing_commas() {
+        let options = ParseOptions {
+            allow_trailing_commas: true,
+            ..Default::default()
+        };
+        let cases = vec![
+            (r#"[1, 2,]"#, Ok(r#"[1, 2]"#)),
+            (r#"{"a": [1,], "b": {},}"#, Ok(r#"{"a": [1], "b": {}}"#)),
+            (r#"[1,, 2]"#, Err("Unexpected token of input")),
+            (r#"[1, 2,,]"#, Err("Unexpected token of input")),
+            (r#"{"a": 1,,}"#, Err("Unexpected token of input")),
+            (r#"{,}"#, Err("Unexpected token of input")),
+        ];
+
+        for (input, expected) in cases {
+            let expected = expected
+                .map(|expected| Parser::new(expected).parse())
+                .map_err(|message| message.to_string());
+            let actual = Parser::with_options(input, options)
+                .try_parse()
+                .map_err(|e| e.message);
+            assert_eq!(actual, expected);
+        }
+    }
+
+    #[test]
+    fn test_allow_comments() {
+        let input = "{\n  // line\n  \"a\": [1, /* block */ 2], \"b\": \"// kept\" /* end */\n}";
+        let options = ParseOptions {
+            allow_comments: true,
+            ..Default::default()
+        };
+        let node = Parser::with_options(input, options).parse();
+        assert_eq!(
+            node,
+            Parser::new(r#"{"a": [1, 2], "b": "// kept"}"#).parse()
+        );
+
+        let mut parser = Parser::new(input);
+        assert!(parser.try_parse().is_err());
+        assert_eq!(
+            parser.lex_error(),
+            Some(&LexError::UnexpectedCharacter {
+                character: '/',
+                offset: 4,
+            })
+        );
+    }
+
+    #[test]
+    fn test_lenient_non_string_keys() {
+        let input = r#"{1: "a", 2.5: "b", true: "c"}"#;
+        let mut parser = Parser::with_options(
+            input,
+            ParseOptions {
+                lenient: true,
+                ..Default::default()
+            },
+        );
+        let node = parser.parse();
+        assert_eq!(node.keys(), vec!["1", "2.5", "true"]);
+        assert_eq!(parser.warnings().len(), 3);
+
+        let cases = vec![r#"{1: "a"}"#, r#"{false: "a"}"#];
+        for input in cases {
+            let mut parser = Parser::new(input);
+            assert_eq!(
+                parser.consume_value().map_err(|e| e.message),
+                Err("Unexpected token of input".to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn test_duplicate_keys() {
+        let cases = vec![
+            (
+                r#"{1: "a", "1": "b"}"#,
+                vec![
+                    "Non-string key converted to \"1\"",
+                    "Key \"1\" collides with another key after converting non-string keys",
+                ],
+            ),
+            (
+                r#"{"true": "a", true: "b"}"#,
+                vec![
+                    "Non-string key converted to \"true\"",
+                    "Key \"true\" collides with another key after converting non-string keys",
+                ],
+            ),
+            (
+                r#"{"a": 1, "b": {"a": 2}, "a": 3}"#,
+                vec!["Duplicate key \"a\""],
+            ),
+            (r#"{"1": "a", "2": "b"}"#, vec![]),
+        ];
+
+        for (input, expected) in cases {
+            let mut parser = Parser::with_options(
+                input,
+                ParseOptions {
+                    lenient: true,
+                    ..Default::default()
+                },
+            );
+            parser.parse();
+            assert_eq!(parser.warnings(), expected);
+        }
+    }
 }
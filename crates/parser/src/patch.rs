@@ -0,0 +1,436 @@
+use std::fmt;
+
+use crate::diff::diff_nodes;
+use crate::node::{array_index, pointer_tokens, Node, SyntaxKind};
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum PatchError {
+    /// The patch or one of its operations is not well formed.
+    InvalidOperation(String),
+    /// A `path` or `from` pointer does not resolve in the document.
+    PathNotFound(String),
+    /// A `test` operation found a different value at the given path.
+    TestFailed(String),
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PatchError::InvalidOperation(message) => {
+                write!(f, "Invalid patch operation: {}", message)
+            }
+            PatchError::PathNotFound(path) => write!(f, "Path not found: {}", path),
+            PatchError::TestFailed(path) => write!(f, "Test failed at {}", path),
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+/// Builds an RFC 6902 JSON Patch that turns `old` into `new`, using `add`,
+/// `remove` and `replace` operations. Arrays are compared by index.
+pub fn json_patch(old: &Node, new: &Node) -> Node {
+    let operations = diff_nodes(old, new)
+        .into_iter()
+        .map(|change| match (change.before, change.after) {
+            (None, after) => operation("add", &change.path, after.as_ref()),
+            (Some(_), None) => operation("remove", &change.path, None),
+            (Some(_), after) => operation("replace", &change.path, after.as_ref()),
+        })
+        .collect();
+    Node::new(SyntaxKind::ArrayLiteralExpression, operations)
+}
+
+/// Applies an RFC 6902 JSON Patch to `doc`. Operations are applied in order
+/// and `doc` is left untouched if any of them fails.
+pub fn apply_patch(doc: &mut Node, patch: &Node) -> Result<(), PatchError> {
+    if patch.kind != SyntaxKind::ArrayLiteralExpression {
+        return Err(PatchError::InvalidOperation(
+            "patch must be an array".to_string(),
+        ));
+    }
+    let mut working = doc.clone();
+    for operation in &patch.children {
+        apply_operation(&mut working, operation)?;
+    }
+    *doc = working;
+    Ok(())
+}
+
+fn apply_operation(doc: &mut Node, operation: &Node) -> Result<(), PatchError> {
+    let op = string_member(operation, "op")?;
+    let path = string_member(operation, "path")?;
+    match op {
+        "add" => add(doc, path, value_member(operation)?.clone()),
+        "remove" => remove(doc, path).map(|_| ()),
+        "replace" => {
+            let value = value_member(operation)?.clone();
+            let target = doc
+                .pointer_mut(path)
+                .ok_or_else(|| PatchError::PathNotFound(path.to_string()))?;
+            target.replace(value);
+            Ok(())
+        }
+        "move" => {
+            let from = string_member(operation, "from")?;
+            if path.starts_with(&format!("{}/", from)) {
+                return Err(PatchError::InvalidOperation(format!(
+                    "cannot move {} into its own child {}",
+                    from, path
+                )));
+            }
+            let value = remove(doc, from)?;
+            add(doc, path, value)
+        }
+        "copy" => {
+            let from = string_member(operation, "from")?;
+            let value = doc
+                .pointer_mut(from)
+                .ok_or_else(|| PatchError::PathNotFound(from.to_string()))?
+                .clone();
+            add(doc, path, value)
+        }
+        "test" => {
+            let expected = value_member(operation)?;
+            match doc.pointer_mut(path) {
+                // RFC 6902 compares values, not how they are written
+                Some(actual) if actual.semantic_eq(expected) => Ok(()),
+                Some(_) => Err(PatchError::TestFailed(path.to_string())),
+                None => Err(PatchError::PathNotFound(path.to_string())),
+            }
+        }
+        _ => Err(PatchError::InvalidOperation(format!(
+            "unknown op \"{}\"",
+            op
+        ))),
+    }
+}
+
+fn add(doc: &mut Node, path: &str, value: Node) -> Result<(), PatchError> {
+    let (parent_path, token) = match split_pointer(path)? {
+        Some(split) => split,
+        None => {
+            doc.replace(value);
+            return Ok(());
+        }
+    };
+    let not_found = || PatchError::PathNotFound(path.to_string());
+    let parent = doc.pointer_mut(parent_path).ok_or_else(not_found)?;
+    match parent.kind {
+        SyntaxKind::ObjectLiteralExpression => {
+            let existing = parent.children.iter_mut().rev().find(|property| {
+                matches!(&property.children[0].kind, SyntaxKind::Identifier(key) if *key == token)
+            });
+            match existing {
+                Some(property) => {
+                    property.children[1].replace(value);
+                }
+                None => parent.children.push(property(&token, value)),
+            }
+            Ok(())
+        }
+        SyntaxKind::ArrayLiteralExpression => {
+            if token == "-" {
+                parent.children.push(value);
+                return Ok(());
+            }
+            match array_index(&token) {
+                Some(index) if index <= parent.children.len() => {
+                    parent.children.insert(index, value);
+                    Ok(())
+                }
+                _ => Err(not_found()),
+            }
+        }
+        _ => Err(not_found()),
+    }
+}
+
+fn remove(doc: &mut Node, path: &str) -> Result<Node, PatchError> {
+    let (parent_path, token) = split_pointer(path)?.ok_or_else(|| {
+        PatchError::InvalidOperation("cannot remove the whole document".to_string())
+    })?;
+    let not_found = || PatchError::PathNotFound(path.to_string());
+    let parent = doc.pointer_mut(parent_path).ok_or_else(not_found)?;
+    match parent.kind {
+        SyntaxKind::ObjectLiteralExpression => {
+            let is_token = |property: &Node| matches!(&property.children[0].kind, SyntaxKind::Identifier(key) if *key == token);
+            let index = parent
+                .children
+                .iter()
+                .rposition(is_token)
+                .ok_or_else(not_found)?;
+            let mut property = parent.children.remove(index);
+            // an earlier member with the same key would take its place
+            parent.children.retain(|property| !is_token(property));
+            Ok(property.children.pop().unwrap())
+        }
+        SyntaxKind::ArrayLiteralExpression => match array_index(&token) {
+            Some(index) if index < parent.children.len() => Ok(parent.children.remove(index)),
+            _ => Err(not_found()),
+        },
+        _ => Err(not_found()),
+    }
+}
+
+/// Splits a pointer into its parent pointer and unescaped last token, or
+/// None for the root pointer.
+fn split_pointer(path: &str) -> Result<Option<(&str, String)>, PatchError> {
+    if pointer_tokens(path).is_none() {
+        return Err(PatchError::InvalidOperation(format!(
+            "invalid pointer \"{}\"",
+            path
+        )));
+    }
+    Ok(path.rfind('/').map(|slash| {
+        let token = pointer_tokens(&path[slash..]).unwrap().remove(0);
+        (&path[..slash], token)
+    }))
+}
+
+fn string_member<'a>(operation: &'a Node, name: &str) -> Result<&'a str, PatchError> {
+    match member(operation, name).map(|value| &value.kind) {
+        Some(SyntaxKind::StringLiteral(value)) => Ok(value),
+        _ => Err(PatchError::InvalidOperation(format!(
+            "missing string member \"{}\"",
+            name
+        ))),
+    }
+}
+
+fn value_member(operation: &Node) -> Result<&Node, PatchError> {
+    member(operation, "value")
+        .ok_or_else(|| PatchError::InvalidOperation("missing member \"value\"".to_string()))
+}
+
+fn member<'a>(object: &'a Node, key: &str) -> Option<&'a Node> {
+    object
+        .children
+        .iter()
+        .rev()
+        .find(|property| {
+            matches!(&property.children[0].kind, SyntaxKind::Identifier(name) if name == key)
+        })
+        .map(|property| &property.children[1])
+}
+
+fn operation(op: &str, path: &str, value: Option<&Node>) -> Node {
+    let mut members = vec![
+        property(
+            "op",
+            Node::new(SyntaxKind::StringLiteral(op.to_string()), vec![]),
+        ),
+        property(
+            "path",
+            Node::new(SyntaxKind::StringLiteral(path.to_string()), vec![]),
+        ),
+    ];
+    if let Some(value) = value {
+        members.push(property("value", value.clone()));
+    }
+    Node::new(SyntaxKind::ObjectLiteralExpression, members)
+}
+
+fn property(key: &str, value: Node) -> Node {
+    Node::new(
+        SyntaxKind::PropertyAssignment,
+        vec![
+            Node::new(SyntaxKind::Identifier(key.to_string()), vec![]),
+            value,
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::Parser;
+
+    #[test]
+    fn test_json_patch() {
+        let cases = vec![
+            (r#"{"a": 1}"#, r#"{"a": 1}"#, r#"[]"#),
+            (
+                r#"{"a": 1, "b": 2}"#,
+                r#"{"a": 3, "c": 4}"#,
+                r#"[
+                    {"op": "replace", "path": "/a", "value": 3},
+                    {"op": "remove", "path": "/b"},
+                    {"op": "add", "path": "/c", "value": 4}
+                ]"#,
+            ),
+            (
+                r#"{"list": [1, 2, 3], "a/b": {"x": true}}"#,
+                r#"{"list": [1, 5], "a/b": {"x": true, "y": null}}"#,
+                r#"[
+                    {"op": "replace", "path": "/list/1", "value": 5},
+                    {"op": "remove", "path": "/list/2"},
+                    {"op": "add", "path": "/a~1b/y", "value": null}
+                ]"#,
+            ),
+            (
+                r#"[1]"#,
+                r#"[1, {"a": []}, 3]"#,
+                r#"[
+                    {"op": "add", "path": "/1", "value": {"a": []}},
+                    {"op": "add", "path": "/2", "value": 3}
+                ]"#,
+            ),
+            (
+                r#"{"a": [1]}"#,
+                r#"[1]"#,
+                r#"[{"op": "replace", "path": "", "value": [1]}]"#,
+            ),
+        ];
+
+        for (old, new, expected) in cases {
+            let old = Parser::new(old).parse();
+            let new = Parser::new(new).parse();
+            assert_eq!(json_patch(&old, &new), Parser::new(expected).parse());
+        }
+    }
+
+    #[test]
+    fn test_apply_patch() {
+        let cases = vec![
+            (
+                r#"{"a": 1}"#,
+                r#"[{"op": "add", "path": "/b", "value": [2]}]"#,
+                r#"{"a": 1, "b": [2]}"#,
+            ),
+            (
+                r#"{"list": [1, 3]}"#,
+                r#"[
+                    {"op": "add", "path": "/list/1", "value": 2},
+                    {"op": "add", "path": "/list/-", "value": 4}
+                ]"#,
+                r#"{"list": [1, 2, 3, 4]}"#,
+            ),
+            (
+                r#"{"a": 1, "b": [1, 2]}"#,
+                r#"[{"op": "remove", "path": "/a"}, {"op": "remove", "path": "/b/0"}]"#,
+                r#"{"b": [2]}"#,
+            ),
+            (
+                r#"{"a": 1, "b": 2, "a": 3}"#,
+                r#"[{"op": "remove", "path": "/a"}]"#,
+                r#"{"b": 2}"#,
+            ),
+            (
+                r#"{"a~b": {"c/d": 1}}"#,
+                r#"[{"op": "replace", "path": "/a~0b/c~1d", "value": null}]"#,
+                r#"{"a~b": {"c/d": null}}"#,
+            ),
+            (
+                r#"{"a": {"x": 1}, "b": []}"#,
+                r#"[{"op": "move", "from": "/a/x", "path": "/b/0"}]"#,
+                r#"{"a": {}, "b": [1]}"#,
+            ),
+            (
+                r#"{"a": {"x": 1}}"#,
+                r#"[{"op": "copy", "from": "/a", "path": "/b"}]"#,
+                r#"{"a": {"x": 1}, "b": {"x": 1}}"#,
+            ),
+            (
+                r#"{"a": [1, {"b": true}]}"#,
+                r#"[{"op": "test", "path": "/a/1", "value": {"b": true}}]"#,
+                r#"{"a": [1, {"b": true}]}"#,
+            ),
+            (
+                r#"{"a": {"x": 1, "y": [2]}}"#,
+                r#"[{"op": "test", "path": "/a", "value": {"y": [2], "x": 1}}]"#,
+                r#"{"a": {"x": 1, "y": [2]}}"#,
+            ),
+            (
+                r#"{"a": [1, 250]}"#,
+                r#"[{"op": "test", "path": "/a", "value": [1.0, 2.5e2]}]"#,
+                r#"{"a": [1, 250]}"#,
+            ),
+            (
+                r#"{"a": 1}"#,
+                r#"[{"op": "replace", "path": "", "value": [1]}]"#,
+                r#"[1]"#,
+            ),
+        ];
+
+        for (doc, patch, expected) in cases {
+            let mut doc = Parser::new(doc).parse();
+            apply_patch(&mut doc, &Parser::new(patch).parse()).unwrap();
+            assert_eq!(doc, Parser::new(expected).parse());
+        }
+    }
+
+    #[test]
+    fn test_apply_patch_errors() {
+        let cases = vec![
+            (
+                r#"[{"op": "test", "path": "/a", "value": 2}]"#,
+                PatchError::TestFailed("/a".to_string()),
+            ),
+            (
+                r#"[{"op": "test", "path": "/list", "value": {}}]"#,
+                PatchError::TestFailed("/list".to_string()),
+            ),
+            (
+                r#"[{"op": "remove", "path": "/missing"}]"#,
+                PatchError::PathNotFound("/missing".to_string()),
+            ),
+            (
+                r#"[{"op": "add", "path": "/list/5", "value": 1}]"#,
+                PatchError::PathNotFound("/list/5".to_string()),
+            ),
+            (
+                r#"[{"op": "move", "from": "/list", "path": "/list/0"}]"#,
+                PatchError::InvalidOperation(
+                    "cannot move /list into its own child /list/0".to_string(),
+                ),
+            ),
+            (
+                r#"[{"op": "rename", "path": "/a"}]"#,
+                PatchError::InvalidOperation("unknown op \"rename\"".to_string()),
+            ),
+            (
+                r#"[{"op": "add", "path": "/b", "value": 1}, {"op": "remove", "path": "/c"}]"#,
+                PatchError::PathNotFound("/c".to_string()),
+            ),
+        ];
+
+        for (patch, expected) in cases {
+            let original = Parser::new(r#"{"a": 1, "list": []}"#).parse();
+            let mut doc = original.clone();
+            assert_eq!(
+                apply_patch(&mut doc, &Parser::new(patch).parse()),
+                Err(expected)
+            );
+            assert_eq!(doc, original);
+        }
+    }
+
+    #[test]
+    fn test_apply_generated_patch() {
+        let cases = vec![
+            (
+                r#"{"a": 1, "b": [1, 2, 3]}"#,
+                r#"{"b": [1, 4], "c": {"d": null}}"#,
+            ),
+            (r#"[{"a": 1}, 2, 3]"#, r#"[{"a": 2, "b": []}]"#),
+            (r#"{"x/y": [true]}"#, r#"{"x/y": [true, false, {}]}"#),
+        ];
+
+        for (old, new) in cases {
+            let mut doc = Parser::new(old).parse();
+            let new = Parser::new(new).parse();
+            let patch = json_patch(&doc, &new);
+            apply_patch(&mut doc, &patch).unwrap();
+            assert_eq!(doc, new);
+        }
+
+        // replacing the last of repeated keys leaves the ones before it, which
+        // no longer count
+        let mut doc = Parser::new(r#"{"a": {"x": 1}, "b": 1, "a": {"x": 2}, "b": 2}"#).parse();
+        let new = Parser::new(r#"{"a": {}, "c": 3}"#).parse();
+        let patch = json_patch(&doc, &new);
+        apply_patch(&mut doc, &patch).unwrap();
+        assert!(doc.semantic_eq(&new), "{}", doc);
+    }
+}
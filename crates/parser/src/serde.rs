@@ -0,0 +1,137 @@
+use crate::{
+    node::{Node, Number, SyntaxKind},
+    value::Value,
+};
+
+impl From<Value> for serde_json::Value {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Null => serde_json::Value::Null,
+            Value::Bool(b) => serde_json::Value::Bool(b),
+            Value::Number(n) => number(n),
+            #[cfg(feature = "decimal")]
+            Value::Decimal(d) => {
+                use rust_decimal::prelude::ToPrimitive;
+                match d.to_i64() {
+                    Some(i) if d.fract().is_zero() => serde_json::Value::from(i),
+                    _ => d.to_f64().map_or(serde_json::Value::Null, number),
+                }
+            }
+            Value::String(s) => serde_json::Value::String(s),
+            Value::Array(elements) => {
+                serde_json::Value::Array(elements.into_iter().map(Into::into).collect())
+            }
+            // later duplicate keys overwrite earlier ones, as in serde_json itself
+            Value::Object(members) => serde_json::Value::Object(
+                members
+                    .into_iter()
+                    .map(|(key, value)| (key, value.into()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Numbers keep the integer or float type they were written with, unlike
+/// those converted through `Value`: `1` becomes an integer and `1.0` a float.
+impl From<&Node> for serde_json::Value {
+    fn from(node: &Node) -> Self {
+        match &node.kind {
+            SyntaxKind::NumberLiteral(n) => number_literal(n),
+            SyntaxKind::ArrayLiteralExpression => {
+                serde_json::Value::Array(node.children.iter().map(Into::into).collect())
+            }
+            SyntaxKind::ObjectLiteralExpression => serde_json::Value::Object(
+                node.children
+                    .iter()
+                    .map(|property| {
+                        let key = match &property.children[0].kind {
+                            SyntaxKind::Identifier(key) => key.clone(),
+                            _ => String::new(),
+                        };
+                        (key, (&property.children[1]).into())
+                    })
+                    .collect(),
+            ),
+            SyntaxKind::PropertyAssignment => (&node.children[1]).into(),
+            _ => node.to_value().into(),
+        }
+    }
+}
+
+impl From<Node> for serde_json::Value {
+    fn from(node: Node) -> Self {
+        (&node).into()
+    }
+}
+
+/// Whole numbers in the i64 range become JSON integers, everything else a
+/// float. NaN and infinities have no JSON form and become null.
+fn number(n: f64) -> serde_json::Value {
+    if n.fract() == 0.0 && n >= i64::MIN as f64 && n < i64::MAX as f64 {
+        return serde_json::Value::from(n as i64);
+    }
+    serde_json::Number::from_f64(n).map_or(serde_json::Value::Null, serde_json::Value::Number)
+}
+
+/// Integers written without a fraction or exponent become i64 or u64 when
+/// they fit; anything else is a float.
+fn number_literal(n: &Number) -> serde_json::Value {
+    if let Ok(i) = n.lexeme.parse::<i64>() {
+        return serde_json::Value::from(i);
+    }
+    if let Ok(u) = n.lexeme.parse::<u64>() {
+        return serde_json::Value::from(u);
+    }
+    serde_json::Number::from_f64(n.value).map_or(serde_json::Value::Null, serde_json::Value::Number)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::parse::Parser;
+
+    #[test]
+    fn test_into_serde_json() {
+        let cases = vec![
+            ("null", json!(null)),
+            ("[1, -2.5, 1e3]", json!([1, -2.5, 1000.0])),
+            (
+                r#"{"a": {"b": [true, "x"]}, "c": false, "a": 2}"#,
+                json!({"a": 2, "c": false}),
+            ),
+        ];
+
+        for (input, expected) in cases {
+            let node = Parser::new(input).parse();
+            assert_eq!(serde_json::Value::from(&node), expected);
+            assert_eq!(serde_json::Value::from(node), expected);
+        }
+    }
+
+    #[test]
+    fn test_into_serde_json_keeps_number_type() {
+        let cases = vec![
+            ("1", json!(1)),
+            ("1.0", json!(1.0)),
+            ("-0", json!(0)),
+            ("2e2", json!(200.0)),
+            ("9223372036854775807", json!(i64::MAX)),
+            ("-9223372036854775808", json!(i64::MIN)),
+            ("18446744073709551615", json!(u64::MAX)),
+            ("18446744073709551616", json!(18446744073709551616.0)),
+            (r#"{"a": [1, 1.5]}"#, json!({"a": [1, 1.5]})),
+        ];
+
+        for (input, expected) in cases {
+            let actual = serde_json::Value::from(&Parser::new(input).parse());
+            assert_eq!(actual, expected, "{}", input);
+            assert_eq!(actual.is_f64(), expected.is_f64(), "{}", input);
+        }
+
+        // `Value` has no lexeme, so a whole float still becomes an integer
+        let value = Parser::new("1.0").parse().to_value();
+        assert_eq!(serde_json::Value::from(value), json!(1));
+    }
+}
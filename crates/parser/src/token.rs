@@ -1,5 +1,7 @@
 use phf::phf_map;
 
+use crate::node::Number;
+
 #[derive(Debug, PartialEq, PartialOrd, Clone)]
 pub enum Token {
     LBrace,
@@ -9,9 +11,17 @@ pub enum Token {
     Colon,
     Comma,
     StringValue(String),
-    NumberValue(f64),
+    NumberValue(Number),
+    #[cfg(feature = "decimal")]
+    DecimalValue(rust_decimal::Decimal),
     BooleanValue(bool),
     NullValue,
+    UndefinedValue,
+    /// A bare word such as an unquoted JSON5 key. Only produced when the lexer
+    /// allows identifiers.
+    Identifier(String),
+    /// Always the last token of a successful tokenize; the parser requires it
+    /// right after the root value.
     End,
 }
 
@@ -28,4 +38,5 @@ pub static KEYWORD_TOKENS: phf::Map<&'static str, Token> = phf_map! {
     "true" => Token::BooleanValue(true),
     "false" => Token::BooleanValue(false),
     "null" => Token::NullValue,
+    "undefined" => Token::UndefinedValue,
 };
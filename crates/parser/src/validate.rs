@@ -0,0 +1,302 @@
+use std::io::{self, Read};
+
+use crate::lexer::{LexError, Lexer};
+use crate::parse::{ParseErrorKind, ParseOptions, Parser, TokenSource};
+use crate::token::Token;
+
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// Checks that `reader` holds a single JSON document, reading it a buffer at a
+/// time without building a tree or keeping its tokens. On error, returns the
+/// byte offset and kind `Parser::try_parse` reports under default options.
+/// Bytes that are not valid UTF-8 are a `Lex` error where they are read,
+/// rather than ahead of any other error as with `Lexer::from_bytes`.
+///
+/// The outer `Result` carries errors from `reader` itself.
+pub fn validate_stream<R: Read>(reader: R) -> io::Result<Result<(), (usize, ParseErrorKind)>> {
+    let mut io_error = None;
+    let tokens = StreamTokens {
+        reader,
+        buffer: vec![0; BUFFER_SIZE],
+        text: String::new(),
+        start: 0,
+        base: 0,
+        pending: Vec::new(),
+        invalid_at: None,
+        eof: false,
+        finished: false,
+        token_start: 0,
+        token_end: 0,
+        io_error: &mut io_error,
+    };
+    let result = Parser::from_source(Box::new(tokens), ParseOptions::default()).validate();
+    match io_error {
+        Some(e) => Err(e),
+        None => Ok(result.map_err(|e| (e.offset, e.kind))),
+    }
+}
+
+/// Lexes a reader with the parser's own `Lexer`, one token at a time. Only
+/// the text from the token being read on is kept, so memory grows with the
+/// longest token rather than with the document.
+struct StreamTokens<'e, R> {
+    reader: R,
+    buffer: Vec<u8>,
+    /// Text decoded so far; everything before `start` has been lexed.
+    text: String,
+    start: usize,
+    /// Byte offset in the stream of `text[0]`.
+    base: usize,
+    /// Bytes read but not yet decoded, such as half of a split character.
+    pending: Vec<u8>,
+    /// Byte offset in the stream of bytes that are not valid UTF-8. Nothing
+    /// past them is decoded.
+    invalid_at: Option<usize>,
+    eof: bool,
+    finished: bool,
+    token_start: usize,
+    token_end: usize,
+    /// Where a failed read is left for `validate_stream`, which ends the
+    /// tokens early.
+    io_error: &'e mut Option<io::Error>,
+}
+
+impl<R: Read> StreamTokens<'_, R> {
+    /// Reads until at least `needed` more bytes are decoded, the input ends
+    /// or it stops being valid UTF-8.
+    fn fill(&mut self, needed: usize) -> io::Result<()> {
+        let target = self.text.len() + needed;
+        while self.text.len() < target && !self.eof && self.invalid_at.is_none() {
+            let read = match self.reader.read(&mut self.buffer) {
+                Ok(0) => {
+                    self.eof = true;
+                    if !self.pending.is_empty() {
+                        self.invalid_at = Some(self.base + self.text.len());
+                    }
+                    break;
+                }
+                Ok(read) => read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            self.pending.extend_from_slice(&self.buffer[..read]);
+            let valid = match std::str::from_utf8(&self.pending) {
+                Ok(_) => self.pending.len(),
+                Err(e) => {
+                    if e.error_len().is_some() {
+                        self.invalid_at = Some(self.base + self.text.len() + e.valid_up_to());
+                    }
+                    e.valid_up_to()
+                }
+            };
+            if let Ok(decoded) = std::str::from_utf8(&self.pending[..valid]) {
+                self.text.push_str(decoded);
+            }
+            self.pending.drain(..valid);
+        }
+        Ok(())
+    }
+
+    /// Drops the text already lexed once it is most of what is kept.
+    fn compact(&mut self) {
+        if self.start >= BUFFER_SIZE && self.start * 2 >= self.text.len() {
+            self.text.drain(..self.start);
+            self.base += self.start;
+            self.start = 0;
+        }
+    }
+}
+
+impl<R: Read> Iterator for StreamTokens<'_, R> {
+    type Item = Result<Token, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        loop {
+            let rest = &self.text[self.start..];
+            let mut lexer = Lexer::new(rest);
+            let token = lexer.next_token();
+            let (start, end) = (lexer.token_start(), lexer.token_end());
+            // a lexer that reached the end of the text may be missing the
+            // rest of the token; read at least as much again and relex it
+            if end == rest.len() && !self.eof && self.invalid_at.is_none() {
+                let needed = rest.len().max(BUFFER_SIZE);
+                if let Err(e) = self.fill(needed) {
+                    *self.io_error = Some(e);
+                    self.finished = true;
+                    return Some(Ok(Token::End));
+                }
+                continue;
+            }
+            let offset = self.base + self.start;
+            let token = match (token, self.invalid_at) {
+                // the lexer needed what follows, and that is not text
+                (Err(_) | Ok(Token::End), Some(invalid)) if end == rest.len() => {
+                    Err(LexError::InvalidUtf8 { offset: invalid })
+                }
+                (token, _) => token.map_err(|e| e.shifted(offset)),
+            };
+            self.token_start = offset + start;
+            self.token_end = offset + end;
+            self.start += end;
+            self.finished = matches!(token, Ok(Token::End) | Err(_));
+            self.compact();
+            return Some(token);
+        }
+    }
+}
+
+impl<R: Read> TokenSource for StreamTokens<'_, R> {
+    fn token_start(&self) -> usize {
+        self.token_start
+    }
+
+    fn token_end(&self) -> usize {
+        self.token_end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Hands out at most `size` bytes per read, to split characters and
+    /// tokens across reads.
+    struct Chunked<'a> {
+        bytes: &'a [u8],
+        size: usize,
+    }
+
+    impl Read for Chunked<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = self.size.min(buf.len()).min(self.bytes.len());
+            buf[..len].copy_from_slice(&self.bytes[..len]);
+            self.bytes = &self.bytes[len..];
+            Ok(len)
+        }
+    }
+
+    fn parse_result(input: &str) -> Result<(), (usize, ParseErrorKind)> {
+        Parser::new(input)
+            .try_parse()
+            .map(|_| ())
+            .map_err(|e| (e.offset, e.kind))
+    }
+
+    fn validate(bytes: &[u8], size: usize) -> Result<(), (usize, ParseErrorKind)> {
+        validate_stream(Chunked { bytes, size }).unwrap()
+    }
+
+    #[test]
+    fn test_validate_stream() {
+        let deep = |depth| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        let cases = vec![
+            "",
+            " \n\t ",
+            r#"{"a": [1, -2.5e+3, true, false, null, "x"], "b": {}}"#,
+            "\u{a0}[\u{2028}1\u{3000}]\u{85}",
+            "[1,]",
+            r#"{"a": 1,}"#,
+            "[1}",
+            r#"{"a" 1}"#,
+            r#"{1: 2}"#,
+            "[1 2]",
+            "[1 @]",
+            "[1 2 @]",
+            "1 2",
+            "[",
+            r#"{"a":"#,
+            "[undefined]",
+            "{undefined: 1}",
+            "[tru]",
+            "[truex]",
+            "[Null]",
+            "[nullé]",
+            "[01]",
+            "[-]",
+            "[1.]",
+            "[1e]",
+            "[.5]",
+            "[1-2]",
+            "[0.5E-10]",
+            "['a']",
+            "[a_b]",
+            "[é]",
+            "// c\n1",
+            r#"["a\"b\\"]"#,
+            r#"["é😀\/"]"#,
+            r#"["\ud83d"]"#,
+            r#"["\ud83dx"]"#,
+            r#"["\ude00"]"#,
+            r#"["\ud83dA"]"#,
+            r#"["\u12G4", 1 2]"#,
+            r#"["\x"]"#,
+            "[\"a\tb\"]",
+            r#"["abc"#,
+            r#"["\u12"#,
+            "\u{feff}1",
+        ];
+        let depths = [127, 128, 129, 200];
+        let inputs = cases
+            .into_iter()
+            .map(str::to_string)
+            .chain(depths.into_iter().map(deep));
+        for input in inputs {
+            for size in [1, 3, BUFFER_SIZE] {
+                assert_eq!(
+                    validate(input.as_bytes(), size),
+                    parse_result(&input),
+                    "{:?} in reads of {}",
+                    input,
+                    size
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_validate_stream_large() {
+        let mut valid = String::from("[");
+        for index in 0..30_000 {
+            if index > 0 {
+                valid.push_str(",\n");
+            }
+            valid.push_str(&format!(
+                r#"{{"id": {}, "name": "item é {}", "tags": ["a", "b"], "ratio": {}.5}}"#,
+                index, index, index
+            ));
+        }
+        valid.push(']');
+        assert!(valid.len() > BUFFER_SIZE * 20);
+        assert_eq!(validate(valid.as_bytes(), 4093), Ok(()));
+
+        let invalid = valid.replacen(r#""id": 29990,"#, r#""id": 29990"#, 1);
+        let error = validate(invalid.as_bytes(), 4093);
+        assert_eq!(error, parse_result(&invalid));
+        assert_eq!(error.unwrap_err().1, ParseErrorKind::Syntax);
+
+        let long_string = format!("[\"{}\", 1 2]", "é".repeat(BUFFER_SIZE * 2));
+        let error = validate(long_string.as_bytes(), 4093);
+        assert_eq!(error, parse_result(&long_string));
+        assert_eq!(error, Err((BUFFER_SIZE * 4 + 7, ParseErrorKind::Syntax)));
+    }
+
+    #[test]
+    fn test_validate_stream_invalid_utf8() {
+        let cases: Vec<(&[u8], Option<usize>)> = vec![
+            (b"[\"a\xff\"]", Some(3)),
+            (b"[\"\xe2\x82\"]", Some(2)),
+            (b"[\"\xed\xa0\x80\"]", Some(2)),
+            (b"[\"\xe2\x82", Some(2)),
+            ("[\"é€😀\"]".as_bytes(), None),
+        ];
+        for (input, offset) in cases {
+            let expected = offset.map_or(Ok(()), |offset| Err((offset, ParseErrorKind::Lex)));
+            for size in [1, 2, BUFFER_SIZE] {
+                assert_eq!(validate(input, size), expected, "{:?}", input);
+            }
+        }
+    }
+}
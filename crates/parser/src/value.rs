@@ -0,0 +1,193 @@
+use crate::{
+    escape::escape_string,
+    node::{Node, SyntaxKind},
+};
+
+/// A JSON value detached from the syntax tree. Objects keep their members in
+/// document order, duplicates included.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    #[cfg(feature = "decimal")]
+    Decimal(rust_decimal::Decimal),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// Serializes the value as JSON without any whitespace. Numbers without a
+    /// fractional part are written as integers, and non-finite numbers, which
+    /// JSON cannot represent, as `null`.
+    pub fn to_string_compact(&self) -> String {
+        let mut s = String::new();
+        self.write_compact(&mut s);
+        s
+    }
+
+    fn write_compact(&self, s: &mut String) {
+        match self {
+            Value::Null => s.push_str("null"),
+            Value::Bool(b) => s.push_str(&b.to_string()),
+            Value::Number(n) if n.is_finite() => s.push_str(&n.to_string()),
+            Value::Number(_) => s.push_str("null"),
+            #[cfg(feature = "decimal")]
+            Value::Decimal(d) => s.push_str(&d.to_string()),
+            Value::String(text) => s.push_str(&escape_string(text)),
+            Value::Array(elements) => {
+                s.push('[');
+                for (index, element) in elements.iter().enumerate() {
+                    if index > 0 {
+                        s.push(',');
+                    }
+                    element.write_compact(s);
+                }
+                s.push(']');
+            }
+            Value::Object(members) => {
+                s.push('{');
+                for (index, (key, value)) in members.iter().enumerate() {
+                    if index > 0 {
+                        s.push(',');
+                    }
+                    s.push_str(&escape_string(key));
+                    s.push(':');
+                    value.write_compact(s);
+                }
+                s.push('}');
+            }
+        }
+    }
+
+    /// Builds the syntax tree for this value, e.g. to hand it to a formatter.
+    pub fn to_node(&self) -> Node {
+        let leaf = |kind| Node::new(kind, vec![]);
+        match self {
+            Value::Null => leaf(SyntaxKind::NullKeyword),
+            Value::Bool(true) => leaf(SyntaxKind::TrueKeyword),
+            Value::Bool(false) => leaf(SyntaxKind::FalseKeyword),
+            Value::Number(n) => leaf(SyntaxKind::NumberLiteral((*n).into())),
+            #[cfg(feature = "decimal")]
+            Value::Decimal(d) => leaf(SyntaxKind::DecimalLiteral(*d)),
+            Value::String(text) => leaf(SyntaxKind::StringLiteral(text.clone())),
+            Value::Array(elements) => Node::new(
+                SyntaxKind::ArrayLiteralExpression,
+                elements.iter().map(Value::to_node).collect(),
+            ),
+            Value::Object(members) => Node::new(
+                SyntaxKind::ObjectLiteralExpression,
+                members
+                    .iter()
+                    .map(|(key, value)| {
+                        Node::new(
+                            SyntaxKind::PropertyAssignment,
+                            vec![leaf(SyntaxKind::Identifier(key.clone())), value.to_node()],
+                        )
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl Node {
+    pub fn to_value(&self) -> Value {
+        match &self.kind {
+            SyntaxKind::NullKeyword => Value::Null,
+            SyntaxKind::TrueKeyword => Value::Bool(true),
+            SyntaxKind::FalseKeyword => Value::Bool(false),
+            SyntaxKind::NumberLiteral(n) => Value::Number(n.value),
+            #[cfg(feature = "decimal")]
+            SyntaxKind::DecimalLiteral(d) => Value::Decimal(*d),
+            SyntaxKind::StringLiteral(s) | SyntaxKind::Identifier(s) => Value::String(s.clone()),
+            SyntaxKind::ArrayLiteralExpression => {
+                Value::Array(self.children.iter().map(Node::to_value).collect())
+            }
+            SyntaxKind::ObjectLiteralExpression => Value::Object(
+                self.children
+                    .iter()
+                    .map(|property| match &property.children[0].kind {
+                        SyntaxKind::Identifier(key) => {
+                            (key.clone(), property.children[1].to_value())
+                        }
+                        _ => (String::new(), property.children[1].to_value()),
+                    })
+                    .collect(),
+            ),
+            SyntaxKind::PropertyAssignment => self.children[1].to_value(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::Parser;
+
+    #[test]
+    fn test_to_value() {
+        let cases = vec![
+            ("null", Value::Null),
+            ("-2.5", Value::Number(-2.5)),
+            (
+                r#"{"a": [true, false, null], "b": {"c": "d"}, "a": 1}"#,
+                Value::Object(vec![
+                    (
+                        "a".to_string(),
+                        Value::Array(vec![Value::Bool(true), Value::Bool(false), Value::Null]),
+                    ),
+                    (
+                        "b".to_string(),
+                        Value::Object(vec![("c".to_string(), Value::String("d".to_string()))]),
+                    ),
+                    ("a".to_string(), Value::Number(1.0)),
+                ]),
+            ),
+            (
+                r#"[[], {}, "x"]"#,
+                Value::Array(vec![
+                    Value::Array(vec![]),
+                    Value::Object(vec![]),
+                    Value::String("x".to_string()),
+                ]),
+            ),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(Parser::new(input).parse().to_value(), expected);
+        }
+    }
+
+    #[test]
+    fn test_to_string_compact() {
+        let cases = vec![
+            ("null", "null"),
+            ("[1.0, -2.5, 1e3, true]", "[1,-2.5,1000,true]"),
+            (
+                r#"{ "a" : [ ], "b\n" : { "c" : "say \"hi\"" } }"#,
+                r#"{"a":[],"b\n":{"c":"say \"hi\""}}"#,
+            ),
+        ];
+
+        for (input, expected) in cases {
+            let value = Parser::new(input).parse().to_value();
+            let compact = value.to_string_compact();
+            assert_eq!(compact, expected);
+            assert_eq!(Parser::new(&compact).parse().to_value(), value);
+        }
+
+        assert_eq!(Value::Number(f64::NAN).to_string_compact(), "null");
+    }
+
+    #[test]
+    fn test_to_node() {
+        let cases = vec![r#"{"a": [1, "x", null], "b": {"c": false}}"#, "[]", "true"];
+
+        for input in cases {
+            let node = Parser::new(input).parse();
+            assert_eq!(node.to_value().to_node(), node);
+        }
+    }
+}
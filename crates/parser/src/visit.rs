@@ -0,0 +1,96 @@
+use crate::node::{Node, SyntaxKind};
+
+/// Callbacks for walking a syntax tree with `walk`. Every method defaults to
+/// visiting the children of its node, so implementors only override the
+/// kinds they care about and call `walk_children` to keep descending.
+pub trait Visitor {
+    fn visit_object(&mut self, node: &Node) {
+        walk_children(self, node);
+    }
+
+    fn visit_array(&mut self, node: &Node) {
+        walk_children(self, node);
+    }
+
+    /// Called for each object member. Its key, an `Identifier`, and its value
+    /// are visited next.
+    fn visit_property(&mut self, node: &Node) {
+        walk_children(self, node);
+    }
+
+    /// Called for strings, numbers, keywords and object keys.
+    fn visit_primitive(&mut self, _node: &Node) {}
+}
+
+/// Dispatches `node` to the matching `Visitor` method.
+pub fn walk<V: Visitor + ?Sized>(node: &Node, visitor: &mut V) {
+    match node.kind {
+        SyntaxKind::ObjectLiteralExpression => visitor.visit_object(node),
+        SyntaxKind::ArrayLiteralExpression => visitor.visit_array(node),
+        SyntaxKind::PropertyAssignment => visitor.visit_property(node),
+        _ => visitor.visit_primitive(node),
+    }
+}
+
+pub fn walk_children<V: Visitor + ?Sized>(visitor: &mut V, node: &Node) {
+    for child in &node.children {
+        walk(child, visitor);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::Parser;
+
+    struct StringCounter {
+        count: usize,
+    }
+
+    impl Visitor for StringCounter {
+        fn visit_primitive(&mut self, node: &Node) {
+            if let SyntaxKind::StringLiteral(_) = node.kind {
+                self.count += 1;
+            }
+        }
+    }
+
+    /// Collects keys without descending into arrays.
+    struct TopLevelKeys {
+        keys: Vec<String>,
+    }
+
+    impl Visitor for TopLevelKeys {
+        fn visit_array(&mut self, _node: &Node) {}
+
+        fn visit_primitive(&mut self, node: &Node) {
+            if let SyntaxKind::Identifier(key) = &node.kind {
+                self.keys.push(key.clone());
+            }
+        }
+    }
+
+    #[test]
+    fn test_count_strings() {
+        let cases = vec![
+            (r#""a""#, 1),
+            (r#"{"a": 1}"#, 0),
+            (r#"{"a": "b", "c": ["d", 1, {"e": "f"}], "g": null}"#, 3),
+        ];
+
+        for (input, expected) in cases {
+            let node = Parser::new(input).parse();
+            let mut counter = StringCounter { count: 0 };
+            walk(&node, &mut counter);
+            assert_eq!(counter.count, expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_override_stops_descent() {
+        let node = Parser::new(r#"{"a": {"b": 1}, "c": [{"d": 2}]}"#).parse();
+        let mut visitor = TopLevelKeys { keys: Vec::new() };
+        walk(&node, &mut visitor);
+        assert_eq!(visitor.keys, vec!["a", "b", "c"]);
+    }
+}